@@ -62,6 +62,23 @@ pub enum LinuxBackend {
     WaylandWithX11Fallback,
 }
 
+/// Who draws the window decorations (title bar and borders) on Wayland.
+/// lokinit's own title bar draws the title with a tiny built-in font, so only the
+/// ASCII characters of the title show up there.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WaylandDecorations {
+    /// Ask the compositor to draw them through xdg-decoration.
+    /// If it refuses or does not support server-side decorations at all (GNOME),
+    /// lokinit draws its own.
+    /// The default option.
+    ServerWithFallback,
+    /// Always draw lokinit's own decorations.
+    ClientSide,
+    /// No decorations at all, the app draws its own title bar and makes it work with
+    /// `window::start_window_move` and `window::start_window_resize`.
+    AppDrawn,
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum AppleGfxApi {
     OpenGl,
@@ -84,6 +101,11 @@ pub struct Platform {
     /// Defaults to X11Only. Wayland implementation is way too unstable right now.
     pub linux_backend: LinuxBackend,
 
+    /// Who draws the window decorations on Wayland, ignored everywhere else.
+    ///
+    /// Defaults to WaylandDecorations::ServerWithFallback.
+    pub wayland_decorations: WaylandDecorations,
+
     /// Which rendering context to create, Metal or OpenGL.
     /// Miniquad always links with Metal.framework (assuming it is always present)
     /// but it links with OpenGL dynamically and only if required.
@@ -103,6 +125,17 @@ pub struct Platform {
     /// TODO: Make it works on web, on web it should make a transparent HTML5 canvas
     /// TODO: Document(and check) what does it actually mean on android. Transparent window?
     pub framebuffer_alpha: bool,
+
+    /// When true, update() and draw() are only called after input,
+    /// `window::schedule_update()`, a resize or the window being exposed,
+    /// and the event loop sleeps in between instead of drawing continuously.
+    /// Other wakeups, like user events and timers, need a `window::schedule_update()`
+    /// to be drawn.
+    /// Good for apps that mostly sit idle, e.g. editors or chat clients.
+    /// Currently supported on Linux (X11 and Wayland) only.
+    ///
+    /// Defaults to false.
+    pub blocking_event_loop: bool,
 }
 
 impl Default for Platform {
@@ -111,16 +144,42 @@ impl Default for Platform {
             linux_x11_gl: LinuxX11Gl::GLXWithEGLFallback,
             swap_interval: None,
             linux_backend: LinuxBackend::X11Only,
+            wayland_decorations: WaylandDecorations::ServerWithFallback,
             apple_gfx_api: AppleGfxApi::OpenGl,
             framebuffer_alpha: false,
+            blocking_event_loop: false,
         }
     }
 }
 
+/// One image of an `Icon`
+#[derive(Debug, Clone)]
+pub struct IconImage {
+    pub width: u32,
+    pub height: u32,
+    /// 8 bit RGBA pixels, row by row from the top left, `width * height * 4` bytes
+    pub rgba: Vec<u8>,
+}
+
+/// Window icon, with the same picture at several sizes.
+/// The desktop picks the closest one to the size it needs, the taskbar,
+/// the window switcher and the title bar all use different sizes.
+#[derive(Debug, Clone, Default)]
+pub struct Icon {
+    pub images: Vec<IconImage>,
+}
+
 #[derive(Debug)]
 pub struct Conf {
     /// Title of the window, defaults to an empty string.
     pub window_title: String,
+    /// Application id used by the desktop to match the window with its .desktop file
+    /// and to group windows in the taskbar. Should be the name of the .desktop file,
+    /// without the extension. Sent as xdg_toplevel app_id on Wayland and WM_CLASS on X11,
+    /// ignored on other platforms.
+    ///
+    /// Default: "", not set
+    pub app_id: String,
     /// The preferred width of the window, ignored on android.
     ///
     /// Default: 800
@@ -145,6 +204,13 @@ pub struct Conf {
     /// Determines if the application user can resize the window
     pub window_resizable: bool,
 
+    /// Window icon, can be changed later with `window::set_window_icon`.
+    /// Only used on X11 right now, on Wayland the icon comes from the .desktop file
+    /// matching `app_id`.
+    ///
+    /// Default: None, the desktop's default icon
+    pub icon: Option<Icon>,
+
     /// Platform specific settings. Hints to OS for context creation, driver-specific
     /// settings etc.
     pub platform: Platform,
@@ -156,12 +222,14 @@ impl Default for Conf {
     fn default() -> Conf {
         Conf {
             window_title: "".to_owned(),
+            app_id: "".to_owned(),
             window_width: 800,
             window_height: 600,
             high_dpi: false,
             fullscreen: false,
             sample_count: 1,
             window_resizable: true,
+            icon: None,
             platform: Default::default(),
         }
     }
@@ -172,12 +240,14 @@ impl Default for Conf {
     fn default() -> Conf {
         Conf {
             window_title: "".to_owned(),
+            app_id: "".to_owned(),
             window_width: 800,
             window_height: 600,
             high_dpi: true,
             fullscreen: true,
             sample_count: 1,
             window_resizable: false,
+            icon: None,
             platform: Default::default(),
         }
     }
@@ -0,0 +1,28 @@
+/// Why the window could not be created, or the event loop had to stop
+#[derive(Debug)]
+pub enum Error {
+    /// There is no display server to connect to, its client libraries are missing
+    /// or it lacks something essential
+    NoDisplayServer(String),
+    /// libGL or libEGL could not be loaded, or does not work with the display
+    GlLoadFailed(String),
+    /// The GL context or the surface to draw to could not be created
+    ContextCreationFailed(String),
+    /// The display server sent a protocol error, or the connection to it broke
+    Protocol(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::NoDisplayServer(reason) => write!(f, "No display server: {}", reason),
+            Error::GlLoadFailed(reason) => write!(f, "Failed to load GL: {}", reason),
+            Error::ContextCreationFailed(reason) => {
+                write!(f, "Failed to create the GL context: {}", reason)
+            }
+            Error::Protocol(reason) => write!(f, "Display server connection failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
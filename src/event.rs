@@ -170,10 +170,17 @@ pub trait EventHandler {
     fn draw(&mut self);
 
     fn resize_event(&mut self, _width: f32, _height: f32) {}
+    /// The window was moved, `x` and `y` are the new `window::window_position`.
+    /// Only sent on X11 right now.
+    fn window_moved_event(&mut self, _x: i32, _y: i32) {}
     fn mouse_motion_event(&mut self, _x: f32, _y: f32) {}
     fn mouse_wheel_event(&mut self, _x: f32, _y: f32) {}
     fn mouse_button_down_event(&mut self, _button: MouseButton, _x: f32, _y: f32) {}
     fn mouse_button_up_event(&mut self, _button: MouseButton, _x: f32, _y: f32) {}
+    /// The mouse cursor moved over the window, mouse_motion_event follows with the position
+    fn mouse_enter_event(&mut self) {}
+    /// The mouse cursor left the window, e.g. to clear hover states
+    fn mouse_leave_event(&mut self) {}
 
     fn char_event(&mut self, _character: char, _keymods: KeyMods, _repeat: bool) {}
 
@@ -181,6 +188,20 @@ pub trait EventHandler {
 
     fn key_up_event(&mut self, _keycode: KeyCode, _keymods: KeyMods) {}
 
+    /// Text being composed with an input method, not yet part of the text.
+    /// Replaces the previous preedit text, an empty one means the composition is over.
+    /// `cursor` is a byte range in `text`, None when the cursor should be hidden.
+    /// Only sent while the input method is enabled with `window::set_ime_enabled`.
+    fn ime_preedit_event(&mut self, _text: &str, _cursor: Option<(usize, usize)>) {}
+
+    /// Text finished with an input method, to be inserted at the cursor.
+    /// Default implementation sends it as char_event's
+    fn ime_commit_event(&mut self, text: &str) {
+        for character in text.chars() {
+            self.char_event(character, KeyMods::default(), false);
+        }
+    }
+
     ///  &mutDefault implementation emulates mouse clicks
     fn touch_event(&mut self, phase: TouchPhase, _id: u64, x: f32, y: f32) {
         match phase {
@@ -196,12 +217,22 @@ pub trait EventHandler {
     /// hardware units instead. And those units may be different from pixels depending on the target platform
     fn raw_mouse_motion(&mut self, _dx: f32, _dy: f32) {}
 
+    /// The window gained or lost the keyboard focus
+    fn focus_event(&mut self, _focused: bool) {}
+
+    /// The window became visible, or it can't be seen anymore - covered by other windows,
+    /// minimized, or on another workspace. Rendering may be paused while it is not visible.
+    /// On Wayland only compositors with xdg-shell version 6 tell, by suspending the window.
+    fn visibility_event(&mut self, _visible: bool) {}
+
     /// Window has been minimized
-    /// Right now is only implemented on Android, and is called on a Pause ndk callback
+    /// Right now is only implemented on Android, where it is called on a Pause ndk callback,
+    /// and on Linux
     fn window_minimized_event(&mut self) {}
 
     /// Window has been restored
-    /// Right now is only implemented on Android, and is called on a Resume ndk callback
+    /// Right now is only implemented on Android, where it is called on a Resume ndk callback,
+    /// and on Linux
     fn window_restored_event(&mut self) {}
 
     /// This event is sent when the userclicks the window's close button
@@ -216,4 +247,34 @@ pub trait EventHandler {
     /// `ctx.dropped_file_count()`, and the path of an individual
     /// file with `ctx.dropped_file_path()`.
     fn files_dropped_event(&mut self) {}
+
+    /// A payload sent with `EventProxy::send`, possibly from another thread.
+    /// Downcast it back to whatever type was sent.
+    fn user_event(&mut self, _payload: Box<dyn std::any::Any + Send>) {}
+
+    /// A timer from `window::set_timeout` or `window::set_interval` is due
+    fn timer_event(&mut self, _id: u64) {}
+}
+
+/// Sends user events to the main loop from any thread, waking it up if it sleeps.
+/// Obtained with `window::event_proxy`, they arrive with `EventHandler::user_event`.
+#[derive(Clone)]
+pub struct EventProxy {
+    send: std::sync::Arc<dyn Fn(Box<dyn std::any::Any + Send>) + Send + Sync>,
+}
+
+impl EventProxy {
+    #[allow(dead_code)]
+    pub(crate) fn new(
+        send: impl Fn(Box<dyn std::any::Any + Send>) + Send + Sync + 'static,
+    ) -> Self {
+        EventProxy {
+            send: std::sync::Arc::new(send),
+        }
+    }
+
+    /// Events sent after the app quit are dropped
+    pub fn send<T: std::any::Any + Send>(&self, payload: T) {
+        (self.send)(Box::new(payload))
+    }
 }
@@ -2,6 +2,7 @@
 // TODO: add safety docs everywhere?
 
 pub mod conf;
+mod error;
 mod event;
 pub mod fs;
 
@@ -13,6 +14,7 @@ pub mod native;
 #[cfg(feature = "log-impl")]
 pub mod log;
 
+pub use error::Error;
 pub use event::*;
 
 pub use native::{gl, NativeDisplay};
@@ -22,8 +24,12 @@ thread_local! {
     #[allow(clippy::type_complexity)]
     static NATIVE_DISPLAY: RefCell<Option<fn (&mut dyn FnMut(&mut dyn crate::NativeDisplay))>> = RefCell::new(None);
 }
+/// Does nothing when there is no window, e.g. after it was closed
 pub(crate) fn with_native_display(f: &mut dyn FnMut(&mut dyn crate::NativeDisplay)) {
-    NATIVE_DISPLAY.with(|d| (d.borrow().as_ref().unwrap())(f))
+    let with = NATIVE_DISPLAY.with(|d| *d.borrow());
+    if let Some(with) = with {
+        with(f)
+    }
 }
 
 // I wish "with_native_display" could be generic over return value, but function
@@ -92,11 +98,47 @@ pub mod window {
         with_native_display!(d, d.cancel_quit())
     }
 
+    /// Asks for one more update() and draw() with `conf::Platform::blocking_event_loop`,
+    /// the loop sleeps until the next event otherwise.
+    /// Input already leads to an update, this is for other wakeups like user events
+    /// and timers, or for draw() itself for as long as an animation runs.
+    /// Does nothing without blocking_event_loop, frames are drawn continuously anyway.
+    pub fn schedule_update() {
+        with_native_display!(d, d.schedule_update())
+    }
+
+    /// A handle for other threads to send events to the main loop.
+    /// None on platforms without user events - only Linux right now.
+    pub fn event_proxy() -> Option<crate::EventProxy> {
+        with_native_display!(d, d.event_proxy())
+    }
+
+    /// Calls `EventHandler::timer_event` once, after `delay`.
+    /// Returns the timer id passed to timer_event, to tell timers apart and for `clear_timer`.
+    /// Timers fire between events, so they are late by up to a frame without
+    /// `conf::Platform::blocking_event_loop`, and on time with it.
+    /// Only works on Linux right now.
+    pub fn set_timeout(delay: std::time::Duration) -> u64 {
+        with_native_display!(d, d.set_timer(delay, false))
+    }
+
+    /// Calls `EventHandler::timer_event` every `interval`, until `clear_timer`.
+    /// Returns the timer id, see `set_timeout`.
+    pub fn set_interval(interval: std::time::Duration) -> u64 {
+        with_native_display!(d, d.set_timer(interval, true))
+    }
+
+    /// Cancels a timer from `set_timeout` or `set_interval`
+    pub fn clear_timer(id: u64) {
+        with_native_display!(d, d.clear_timer(id))
+    }
+
     /// Capture mouse cursor to the current window
     /// On desktop this will bound cursor to windows border
     /// NOTICE: on desktop cursor will not be automatically released after window lost focus
-    ///         so set_cursor_grab(false) on window's focus lost is recommended.
-    /// TODO: implement window focus events
+    ///         so set_cursor_grab(false) on focus_event(false) is recommended.
+    /// On Wayland a grabbed cursor that is also hidden is locked in place,
+    /// only raw_mouse_motion is reported until it is shown or released.
     pub fn set_cursor_grab(grab: bool) {
         with_native_display!(d, d.set_cursor_grab(grab))
     }
@@ -116,10 +158,83 @@ pub mod window {
         with_native_display!(d, d.set_window_size(new_width, new_height))
     }
 
+    /// Move the window, so that its top left corner is at `x`, `y` on the screen,
+    /// not counting the title bar and borders.
+    /// Only works on X11 right now - Wayland doesn't let apps place their windows.
+    pub fn set_window_position(x: i32, y: i32) {
+        with_native_display!(d, d.set_window_position(x, y))
+    }
+
+    /// Position of the window's top left corner on the screen, as set by
+    /// `set_window_position`. (0, 0) where it is not known, like on Wayland.
+    pub fn window_position() -> (i32, i32) {
+        with_native_display!(d, d.window_position())
+    }
+
     pub fn set_fullscreen(fullscreen: bool) {
         with_native_display!(d, d.set_fullscreen(fullscreen))
     }
 
+    /// Whether the window is fullscreen right now. Follows what the window manager did,
+    /// so it changes a little after `set_fullscreen` and also when the user toggles it.
+    /// Only works on Linux right now.
+    pub fn is_fullscreen() -> bool {
+        with_native_display!(d, d.is_fullscreen())
+    }
+
+    /// Minimize (iconify) the window. It is up to the user to bring it back.
+    /// Only works on Linux right now.
+    pub fn minimize() {
+        with_native_display!(d, d.minimize())
+    }
+
+    /// Only works on Linux right now.
+    pub fn set_maximized(maximized: bool) {
+        with_native_display!(d, d.set_maximized(maximized))
+    }
+
+    /// Whether the window is maximized right now, follows the window manager like `is_fullscreen`.
+    /// Only works on Linux right now.
+    pub fn is_maximized() -> bool {
+        with_native_display!(d, d.is_maximized())
+    }
+
+    /// Whether the window is minimized right now.
+    /// On Wayland the compositor doesn't tell: the window counts as minimized
+    /// from `minimize` until it is activated again.
+    /// Only works on Linux right now.
+    pub fn is_minimized() -> bool {
+        with_native_display!(d, d.is_minimized())
+    }
+
+    /// Set the application's window title.
+    /// Only works on Linux right now.
+    pub fn set_window_title(title: &str) {
+        with_native_display!(d, d.set_window_title(title))
+    }
+
+    /// Replace the window icon, e.g. to draw a badge into it.
+    /// An icon without images goes back to the desktop's default one.
+    /// Only works on X11 right now, see `Conf::icon`.
+    pub fn set_window_icon(icon: &conf::Icon) {
+        with_native_display!(d, d.set_window_icon(icon))
+    }
+
+    /// Let the user move the window with the mouse, as if the title bar was dragged.
+    /// For apps drawing their own title bar: call it from `mouse_button_down_event`
+    /// when the press is over the title bar.
+    /// Only works on Wayland right now.
+    pub fn start_window_move() {
+        with_native_display!(d, d.start_window_move())
+    }
+
+    /// Let the user resize the window with the mouse, as if the given edge was dragged.
+    /// Should be called from `mouse_button_down_event`, same as `start_window_move`.
+    /// Only works on Wayland right now.
+    pub fn start_window_resize(edge: ResizeEdge) {
+        with_native_display!(d, d.start_window_resize(edge))
+    }
+
     /// Get current OS clipboard value
     pub fn clipboard_get() -> Option<String> {
         with_native_display!(d, d.clipboard_get())
@@ -150,11 +265,39 @@ pub mod window {
         with_native_display!(d, d.show_keyboard(show))
     }
 
+    /// Enable or disable the input method (IME), disabled by default.
+    /// While enabled, text composed with the input method arrives with
+    /// `ime_preedit_event` and `ime_commit_event`, and the input method may
+    /// take keys for itself - so it is better enabled only while editing text.
+    /// Only works on Linux right now.
+    pub fn set_ime_enabled(enabled: bool) {
+        with_native_display!(d, d.set_ime_enabled(enabled))
+    }
+
+    /// Area of the text cursor, in the same pixels as mouse events.
+    /// The input method shows its candidate window next to it.
+    pub fn set_ime_cursor_area(x: f32, y: f32, width: f32, height: f32) {
+        with_native_display!(d, d.set_ime_cursor_area(x, y, width, height))
+    }
+
     pub fn get_gl_proc_addr(procname: &str) -> Option<unsafe extern "C" fn()> {
         with_native_display!(d, d.get_gl_proc_addr(procname))
     }
 }
 
+/// Window edge or corner to resize the window from
+#[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
+pub enum ResizeEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
 pub enum CursorIcon {
     Default,
@@ -172,33 +315,29 @@ pub enum CursorIcon {
 }
 
 /// Start lokinit.
+/// Errors are printed to stderr, `try_start` returns them instead.
 pub fn start<F>(conf: conf::Conf, f: F)
+where
+    F: 'static + FnOnce() -> Box<dyn EventHandler>,
+{
+    if let Err(err) = try_start(conf, f) {
+        eprintln!("lokinit: {}", err);
+    }
+}
+
+/// Same as `start`, but returns why the window could not be opened or the event loop
+/// had to stop. Returns `Ok` once the app quits, after the window was closed.
+/// Only Linux reports errors right now.
+pub fn try_start<F>(conf: conf::Conf, f: F) -> Result<(), Error>
 where
     F: 'static + FnOnce() -> Box<dyn EventHandler>,
 {
     #[cfg(target_os = "linux")]
     {
         let mut f = Some(f);
-        let f = &mut f;
-        match conf.platform.linux_backend {
-            conf::LinuxBackend::X11Only => {
-                native::linux_x11::run(&conf, f).expect("X11 backend failed")
-            }
-            conf::LinuxBackend::WaylandOnly => {
-                native::linux_wayland::run(&conf, f).expect("Wayland backend failed")
-            }
-            conf::LinuxBackend::X11WithWaylandFallback => {
-                if native::linux_x11::run(&conf, f).is_none() {
-                    eprintln!("Failed to initialize through X11! Trying wayland instead");
-                    native::linux_wayland::run(&conf, f);
-                }
-            }
-            conf::LinuxBackend::WaylandWithX11Fallback => {
-                if native::linux_wayland::run(&conf, f).is_none() {
-                    eprintln!("Failed to initialize through wayland! Trying X11 instead");
-                    native::linux_x11::run(&conf, f);
-                }
-            }
+        match create_backend(&conf, &mut f)? {
+            EventPumpBackend::X11(mut window) => native::linux_x11::run(&mut window)?,
+            EventPumpBackend::Wayland(mut window) => native::linux_wayland::run(&mut window)?,
         }
     }
 
@@ -221,4 +360,115 @@ where
     unsafe {
         native::ios::run(conf, f);
     }
+
+    Ok(())
+}
+
+/// A window with its event loop driven by the app, see `create_window`.
+#[cfg(target_os = "linux")]
+pub struct EventPump {
+    backend: EventPumpBackend,
+}
+
+#[cfg(target_os = "linux")]
+enum EventPumpBackend {
+    X11(Box<native::linux_x11::X11Window>),
+    Wayland(Box<native::linux_wayland::WaylandWindow>),
+}
+
+#[cfg(target_os = "linux")]
+impl EventPump {
+    /// Dispatches the events that already arrived to the event handler, without waiting.
+    /// Returns false once the app should quit - the window was closed or `window::order_quit`
+    /// was called.
+    pub fn poll_events(&mut self) -> Result<bool, Error> {
+        self.pump_events(Some(std::time::Duration::ZERO))
+    }
+
+    /// Same as `poll_events`, but first waits up to `timeout` for events to arrive,
+    /// or forever on None. User events and timers end the wait as well.
+    pub fn pump_events(&mut self, timeout: Option<std::time::Duration>) -> Result<bool, Error> {
+        match &mut self.backend {
+            EventPumpBackend::X11(window) => window.pump_events(timeout),
+            EventPumpBackend::Wayland(window) => Ok(window.pump_events(timeout)?),
+        }
+    }
+
+    /// Whether it is time to draw the next frame. On Wayland swap_buffers does not wait
+    /// for the display, the compositor tells when it wants the next frame instead -
+    /// never while the window is hidden. Until then, `pump_events(None)` sleeps until
+    /// it is time, drawing anyway only burns CPU. Always true on X11, where swap_buffers
+    /// waits for vsync itself.
+    pub fn frame_ready(&self) -> bool {
+        match &self.backend {
+            EventPumpBackend::X11(_) => true,
+            EventPumpBackend::Wayland(window) => window.frame_ready(),
+        }
+    }
+
+    /// Presents the frame drawn since the last call
+    pub fn swap_buffers(&mut self) {
+        match &mut self.backend {
+            EventPumpBackend::X11(window) => window.swap_buffers(),
+            EventPumpBackend::Wayland(window) => window.swap_buffers(),
+        }
+    }
+
+    /// The event handler created by `create_window`
+    pub fn event_handler(&mut self) -> &mut dyn EventHandler {
+        match &mut self.backend {
+            EventPumpBackend::X11(window) => window.event_handler(),
+            EventPumpBackend::Wayland(window) => window.event_handler(),
+        }
+    }
+}
+
+/// Opens the window like `start`, but leaves the event loop to the app: events are
+/// dispatched with `EventPump::poll_events` or `EventPump::pump_events`, and frames
+/// are presented with `EventPump::swap_buffers`. `update()` and `draw()` are never called,
+/// and `conf::Platform::blocking_event_loop` is up to the timeouts the app passes.
+/// The GL context is current on the calling thread, `window::*` functions work as usual.
+/// For embedding lokinit in engines and test harnesses with a main loop of their own.
+/// Only works on Linux right now.
+#[cfg(target_os = "linux")]
+pub fn create_window<F>(conf: conf::Conf, f: F) -> Result<EventPump, Error>
+where
+    F: 'static + FnOnce() -> Box<dyn EventHandler>,
+{
+    let backend = create_backend(&conf, &mut Some(f))?;
+    Ok(EventPump { backend })
+}
+
+/// Opens the window with the backend picked by `conf.platform.linux_backend`
+#[cfg(target_os = "linux")]
+fn create_backend<F>(conf: &conf::Conf, f: &mut Option<F>) -> Result<EventPumpBackend, Error>
+where
+    F: 'static + FnOnce() -> Box<dyn EventHandler>,
+{
+    let x11 = |f: &mut Option<F>| {
+        native::linux_x11::create(conf, f).map(|window| EventPumpBackend::X11(Box::new(window)))
+    };
+    let wayland = |f: &mut Option<F>| {
+        native::linux_wayland::create(conf, f)
+            .map(|window| EventPumpBackend::Wayland(Box::new(window)))
+            .map_err(Error::from)
+    };
+    match conf.platform.linux_backend {
+        conf::LinuxBackend::X11Only => x11(f),
+        conf::LinuxBackend::WaylandOnly => wayland(f),
+        conf::LinuxBackend::X11WithWaylandFallback => x11(f).or_else(|err| {
+            eprintln!(
+                "Failed to initialize through X11: {}! Trying wayland instead",
+                err
+            );
+            wayland(f)
+        }),
+        conf::LinuxBackend::WaylandWithX11Fallback => wayland(f).or_else(|err| {
+            eprintln!(
+                "Failed to initialize through wayland: {}! Trying X11 instead",
+                err
+            );
+            x11(f)
+        }),
+    }
 }
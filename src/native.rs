@@ -10,6 +10,8 @@ pub(crate) struct NativeDisplayData {
     pub high_dpi: bool,
     pub quit_requested: bool,
     pub quit_ordered: bool,
+    pub blocking_event_loop: bool,
+    pub update_requested: bool,
 }
 
 impl Default for NativeDisplayData {
@@ -21,10 +23,21 @@ impl Default for NativeDisplayData {
             high_dpi: false,
             quit_requested: false,
             quit_ordered: false,
+            blocking_event_loop: false,
+            // the first frame is always drawn
+            update_requested: true,
         }
     }
 }
 
+impl NativeDisplayData {
+    /// Whether to call update() and draw() on this iteration of the event loop
+    pub fn take_update_request(&mut self) -> bool {
+        let update_requested = std::mem::take(&mut self.update_requested);
+        !self.blocking_event_loop || update_requested
+    }
+}
+
 pub trait NativeDisplay: std::any::Any {
     fn screen_size(&self) -> (f32, f32);
     fn dpi_scale(&self) -> f32;
@@ -32,12 +45,39 @@ pub trait NativeDisplay: std::any::Any {
     fn order_quit(&mut self);
     fn request_quit(&mut self);
     fn cancel_quit(&mut self);
+    fn schedule_update(&mut self) {}
+    fn event_proxy(&mut self) -> Option<crate::EventProxy> {
+        None
+    }
+    fn set_timer(&mut self, _delay: std::time::Duration, _repeat: bool) -> u64 {
+        0
+    }
+    fn clear_timer(&mut self, _id: u64) {}
 
     fn set_cursor_grab(&mut self, _grab: bool);
     fn show_mouse(&mut self, _shown: bool);
     fn set_mouse_cursor(&mut self, _cursor_icon: crate::CursorIcon);
     fn set_window_size(&mut self, _new_width: u32, _new_height: u32);
     fn set_fullscreen(&mut self, _fullscreen: bool);
+    fn is_fullscreen(&self) -> bool {
+        false
+    }
+    fn minimize(&mut self) {}
+    fn set_maximized(&mut self, _maximized: bool) {}
+    fn is_maximized(&self) -> bool {
+        false
+    }
+    fn is_minimized(&self) -> bool {
+        false
+    }
+    fn set_window_position(&mut self, _x: i32, _y: i32) {}
+    fn window_position(&self) -> (i32, i32) {
+        (0, 0)
+    }
+    fn set_window_title(&mut self, _title: &str) {}
+    fn set_window_icon(&mut self, _icon: &crate::conf::Icon) {}
+    fn start_window_move(&mut self) {}
+    fn start_window_resize(&mut self, _edge: crate::ResizeEdge) {}
     fn clipboard_get(&mut self) -> Option<String>;
     fn clipboard_set(&mut self, _data: &str);
     fn dropped_file_count(&mut self) -> usize {
@@ -50,6 +90,8 @@ pub trait NativeDisplay: std::any::Any {
         None
     }
     fn show_keyboard(&mut self, _show: bool) {}
+    fn set_ime_enabled(&mut self, _enabled: bool) {}
+    fn set_ime_cursor_area(&mut self, _x: f32, _y: f32, _width: f32, _height: f32) {}
     #[cfg(target_vendor = "apple")]
     fn apple_gfx_api(&self) -> crate::conf::AppleGfxApi;
     #[cfg(target_vendor = "apple")]
@@ -89,4 +131,13 @@ pub mod ios;
 #[cfg(any(target_os = "android", target_os = "linux"))]
 pub mod egl;
 
+#[cfg(target_os = "linux")]
+pub mod uri_list;
+
+#[cfg(target_os = "linux")]
+pub mod user_events;
+
+#[cfg(target_os = "linux")]
+pub mod timers;
+
 pub mod gl;
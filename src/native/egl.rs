@@ -253,6 +253,8 @@ pub enum EglError {
     NoDisplay,
     InitializeFailed,
     CreateContextFailed,
+    CreateSurfaceFailed,
+    MakeCurrentFailed,
 }
 
 pub struct Egl {}
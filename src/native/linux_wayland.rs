@@ -1,22 +1,75 @@
 #![allow(dead_code)]
 
 mod libwayland_client;
+mod libwayland_cursor;
 mod libwayland_egl;
+mod libxkbcommon;
 
+mod cursor;
+mod data_device;
 mod decorations;
 mod extensions;
+mod pointer_grab;
+mod scale;
 mod shm;
+mod text_input;
+mod touch;
 
 use libwayland_client::*;
 use libwayland_egl::*;
+use libxkbcommon::*;
 
 use crate::{
-    event::EventHandler,
-    native::{egl, NativeDisplayData},
+    conf::WaylandDecorations,
+    event::{EventHandler, KeyCode, KeyMods, MouseButton},
+    native::{egl, linux_x11::keycodes, timers, user_events::UserEvents, NativeDisplayData},
+    CursorIcon, ResizeEdge,
 };
 
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[macro_export]
+macro_rules! wl_request_constructor {
+    ($libwayland:expr, $instance:expr, $request_name:expr, $interface:expr) => {
+        wl_request_constructor!($libwayland, $instance, $request_name, $interface, ())
+    };
+
+    ($libwayland:expr, $instance:expr, $request_name:expr, $interface:expr, $($arg:expr),*) => {{
+        let id: *mut wl_proxy;
+
+        id = ($libwayland.wl_proxy_marshal_constructor)(
+            $instance as _,
+            $request_name,
+            $interface as _,
+            std::ptr::null_mut::<std::ffi::c_void>(),
+            $($arg,)*
+        );
+
+        id as *mut _
+    }};
+}
+
+#[macro_export]
+macro_rules! wl_request {
+    ($libwayland:expr, $instance:expr, $request_name:expr) => {
+        wl_request!($libwayland, $instance, $request_name, ())
+    };
+
+    ($libwayland:expr, $instance:expr, $request_name:expr, $($arg:expr),*) => {{
+        ($libwayland.wl_proxy_marshal)(
+            $instance as _,
+            $request_name,
+            $($arg,)*
+        )
+    }};
+}
+
 pub struct WaylandDisplay {
     client: LibWaylandClient,
+    wdisplay: *mut wl_display,
     // this is libwayland-egl.so, a library with ~4 functions
     // not the libEGL.so(which will be loaded, but not here)
     egl: LibWaylandEgl,
@@ -27,15 +80,73 @@ pub struct WaylandDisplay {
     surface: *mut wl_surface,
     decoration_manager: *mut extensions::xdg_decoration::zxdg_decoration_manager_v1,
     viewporter: *mut extensions::viewporter::wp_viewporter,
+    cursor_shape_manager: *mut extensions::cursor_shape::wp_cursor_shape_manager_v1,
+    fractional_scale_manager: *mut extensions::fractional_scale::wp_fractional_scale_manager_v1,
+    pointer_constraints: *mut extensions::pointer_constraints::zwp_pointer_constraints_v1,
+    relative_pointer_manager: *mut extensions::relative_pointer::zwp_relative_pointer_manager_v1,
+    text_input_manager: *mut extensions::text_input::zwp_text_input_manager_v3,
     shm: *mut wl_shm,
+    data_device_manager: *mut wl_data_device_manager,
     seat: *mut wl_seat,
+    // registry name and version of wl_seat, bound only after the display
+    // got into the thread local, because seat listeners rely on it
+    seat_global: Option<(u32, u32)>,
 
     egl_window: *mut wl_egl_window,
     pointer: *mut wl_pointer,
     keyboard: *mut wl_keyboard,
+    touch: touch::Touch,
+    text_input: text_input::TextInput,
     focused_window: *mut wl_surface,
-    //xkb_state: xkb::XkbState,
+    // surface under the pointer, decorations have their own surfaces
+    pointer_focus: *mut wl_surface,
+    // serial of the last wl_pointer.enter, required to change the cursor
+    pointer_serial: u32,
+    // serial of the last keyboard or pointer input, required to set the clipboard
+    input_serial: u32,
+    mouse_x: f32,
+    mouse_y: f32,
+    cursor_icon: CursorIcon,
+    cursor_visible: bool,
+    cursor_images: cursor::CursorImages,
+    pointer_grab: pointer_grab::PointerGrab,
+    title: String,
+    xkb: LibXkbCommon,
+    xkb_context: *mut xkb_context,
+    xkb_keymap: *mut xkb_keymap,
+    xkb_state: *mut xkb_state,
+    key_repeat: KeyRepeat,
+    data_device: data_device::DataDevice,
+    dropped_files: Vec<std::path::PathBuf>,
+    user_events: Option<Arc<UserEvents>>,
+    timers: timers::Timers,
     decorations: Option<decorations::Decorations>,
+    // the window is supposed to have fallback decorations, they are hidden while fullscreen
+    fallback_decorations: bool,
+    decoration_conf: WaylandDecorations,
+    // subcompositor, viewporter and shm are all there
+    can_draw_decorations: bool,
+    scaling: scale::Scaling,
+    // window size in surface coordinates, without decorations
+    logical_size: (i32, i32),
+    fullscreen: bool,
+    maximized: bool,
+    activated: bool,
+    visible: bool,
+    // xdg-shell has no minimized state, a window counts as minimized from asking for it
+    // until it is activated again. Suspension is only a visibility_event, it also
+    // happens on other workspaces or behind other windows.
+    minimized: bool,
+    // what window_minimized_event and window_restored_event last told the app,
+    // they are sent from the main loop
+    minimized_notified: bool,
+    resizable: bool,
+    // outer size to go back to after leaving fullscreen or maximized state
+    windowed_size: (i32, i32),
+    // resize_event for a size set by the app itself, delivered from the main loop
+    pending_resize: Option<(i32, i32)>,
+    // a wl_surface.frame callback is in flight, the compositor is not ready for a new frame yet
+    frame_pending: bool,
     closed: bool,
 
     data: NativeDisplayData,
@@ -61,16 +172,189 @@ impl crate::native::NativeDisplay for WaylandDisplay {
     fn cancel_quit(&mut self) {
         self.data.quit_requested = false;
     }
+    fn schedule_update(&mut self) {
+        self.data.update_requested = true;
+    }
+    fn event_proxy(&mut self) -> Option<crate::EventProxy> {
+        self.user_events.as_ref().map(UserEvents::proxy)
+    }
+    fn set_timer(&mut self, delay: Duration, repeat: bool) -> u64 {
+        self.timers.set(delay, repeat)
+    }
+    fn clear_timer(&mut self, id: u64) {
+        self.timers.clear(id);
+    }
+
+    fn set_cursor_grab(&mut self, grab: bool) {
+        self.pointer_grab.grab = grab;
+        unsafe {
+            pointer_grab::update_constraint(self);
+        }
+    }
+    fn show_mouse(&mut self, shown: bool) {
+        self.cursor_visible = shown;
+        unsafe {
+            cursor::update_cursor(self);
+            pointer_grab::update_constraint(self);
+        }
+    }
+    fn set_mouse_cursor(&mut self, cursor_icon: CursorIcon) {
+        self.cursor_icon = cursor_icon;
+        unsafe {
+            cursor::update_cursor(self);
+        }
+    }
+    fn set_window_size(&mut self, new_width: u32, new_height: u32) {
+        // the compositor dictates the size of fullscreen and maximized windows
+        if self.fullscreen || self.maximized {
+            return;
+        }
+        let (width, height) = self.outer_size(new_width as _, new_height as _);
+        self.windowed_size = (width, height);
+        unsafe {
+            if !self.resizable {
+                self.set_size_limits(width, height);
+            }
+            if let Some(size) = self.resize(width, height) {
+                self.pending_resize = Some(size);
+            }
+        }
+    }
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        unsafe {
+            if fullscreen {
+                wl_request!(
+                    self.client,
+                    self.xdg_toplevel,
+                    extensions::xdg_shell::xdg_toplevel::set_fullscreen,
+                    std::ptr::null_mut::<wl_output>()
+                );
+            } else {
+                wl_request!(
+                    self.client,
+                    self.xdg_toplevel,
+                    extensions::xdg_shell::xdg_toplevel::unset_fullscreen
+                );
+            }
+        }
+    }
+    fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+    fn minimize(&mut self) {
+        unsafe {
+            wl_request!(
+                self.client,
+                self.xdg_toplevel,
+                extensions::xdg_shell::xdg_toplevel::set_minimized
+            );
+        }
+        self.minimized = true;
+    }
+    fn set_maximized(&mut self, maximized: bool) {
+        unsafe {
+            if maximized {
+                wl_request!(
+                    self.client,
+                    self.xdg_toplevel,
+                    extensions::xdg_shell::xdg_toplevel::set_maximized
+                );
+            } else {
+                wl_request!(
+                    self.client,
+                    self.xdg_toplevel,
+                    extensions::xdg_shell::xdg_toplevel::unset_maximized
+                );
+            }
+        }
+    }
+    fn is_maximized(&self) -> bool {
+        self.maximized
+    }
+    fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+    fn set_window_title(&mut self, title: &str) {
+        // A C string ends at the first NUL, so does the title
+        let title = title.split('\0').next().unwrap();
+        self.title = title.to_owned();
+        let title = std::ffi::CString::new(title).unwrap();
+        unsafe {
+            wl_request!(
+                self.client,
+                self.xdg_toplevel,
+                extensions::xdg_shell::xdg_toplevel::set_title,
+                title.as_ptr()
+            );
+            if let Some(ref mut decorations) = self.decorations {
+                decorations.set_title(&mut self.client, self.shm, &self.title);
+                // the title bar is a synchronized subsurface, it shows with the next frame
+                self.data.update_requested = true;
+            }
+        }
+    }
+    fn start_window_move(&mut self) {
+        if self.seat.is_null() {
+            return;
+        }
+        unsafe {
+            wl_request!(
+                self.client,
+                self.xdg_toplevel,
+                extensions::xdg_shell::xdg_toplevel::r#move,
+                self.seat,
+                self.input_serial
+            );
+        }
+    }
+    fn start_window_resize(&mut self, edge: ResizeEdge) {
+        use extensions::xdg_shell::*;
 
-    fn set_cursor_grab(&mut self, _grab: bool) {}
-    fn show_mouse(&mut self, _shown: bool) {}
-    fn set_mouse_cursor(&mut self, _cursor_icon: crate::CursorIcon) {}
-    fn set_window_size(&mut self, _new_width: u32, _new_height: u32) {}
-    fn set_fullscreen(&mut self, _fullscreen: bool) {}
+        if self.seat.is_null() {
+            return;
+        }
+        let edge = match edge {
+            ResizeEdge::Top => XDG_TOPLEVEL_RESIZE_EDGE_TOP,
+            ResizeEdge::Bottom => XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM,
+            ResizeEdge::Left => XDG_TOPLEVEL_RESIZE_EDGE_LEFT,
+            ResizeEdge::Right => XDG_TOPLEVEL_RESIZE_EDGE_RIGHT,
+            ResizeEdge::TopLeft => XDG_TOPLEVEL_RESIZE_EDGE_TOP_LEFT,
+            ResizeEdge::TopRight => XDG_TOPLEVEL_RESIZE_EDGE_TOP_RIGHT,
+            ResizeEdge::BottomLeft => XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_LEFT,
+            ResizeEdge::BottomRight => XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_RIGHT,
+        };
+        unsafe {
+            wl_request!(
+                self.client,
+                self.xdg_toplevel,
+                xdg_toplevel::resize,
+                self.seat,
+                self.input_serial,
+                edge
+            );
+        }
+    }
     fn clipboard_get(&mut self) -> Option<String> {
-        None
+        unsafe { data_device::clipboard_get(self) }
+    }
+    fn clipboard_set(&mut self, data: &str) {
+        unsafe { data_device::clipboard_set(self, data) }
+    }
+    fn dropped_file_count(&mut self) -> usize {
+        self.dropped_files.len()
+    }
+    fn dropped_file_bytes(&mut self, index: usize) -> Option<Vec<u8>> {
+        std::fs::read(self.dropped_files.get(index)?).ok()
+    }
+    fn dropped_file_path(&mut self, index: usize) -> Option<std::path::PathBuf> {
+        self.dropped_files.get(index).cloned()
+    }
+    fn set_ime_enabled(&mut self, enabled: bool) {
+        unsafe { text_input::set_enabled(self, enabled) }
+    }
+    fn set_ime_cursor_area(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        unsafe { text_input::set_cursor_area(self, x, y, width, height) }
     }
-    fn clipboard_set(&mut self, _data: &str) {}
     fn as_any(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -79,6 +363,280 @@ impl crate::native::NativeDisplay for WaylandDisplay {
         (self.get_procaddr.as_ref().unwrap())(procname)
     }
 }
+
+impl WaylandDisplay {
+    /// Window size including the fallback decorations, if any
+    fn outer_size(&self, width: i32, height: i32) -> (i32, i32) {
+        if self.decorations.is_some() {
+            (
+                width + decorations::Decorations::WIDTH * 2,
+                height + decorations::Decorations::BAR_HEIGHT + decorations::Decorations::WIDTH,
+            )
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Shows or hides the fallback decorations, keeping the size of the window content
+    unsafe fn set_fallback_decorations(&mut self, enabled: bool) {
+        if enabled == self.decorations.is_some() {
+            return;
+        }
+        let (width, height) = self.logical_size;
+        if enabled {
+            match decorations::Decorations::new(self, width, height) {
+                Ok(decorations) => self.decorations = Some(decorations),
+                Err(error) => {
+                    eprintln!("Failed to draw decorations: {}", error);
+                    return;
+                }
+            }
+        } else if let Some(decorations) = self.decorations.take() {
+            decorations.destroy(&mut self.client);
+        }
+
+        let (outer_width, outer_height) = self.outer_size(width, height);
+        if !self.fullscreen && !self.maximized {
+            self.windowed_size = (outer_width, outer_height);
+            if !self.resizable {
+                self.set_size_limits(outer_width, outer_height);
+            }
+        }
+    }
+
+    /// Mouse button over the fallback decorations
+    unsafe fn decoration_button(&mut self, button: u32, pressed: bool) {
+        use crate::native::NativeDisplay;
+        use extensions::xdg_shell::xdg_toplevel;
+
+        let Some(ref mut decorations) = self.decorations else {
+            return;
+        };
+        let area = decorations.hovered;
+        // buttons act on release, if it happens over the same button
+        let clicked = match area {
+            Some(decorations::Area::Button(decoration_button)) if button == BTN_LEFT => {
+                if pressed {
+                    decorations.pressed = Some(decoration_button);
+                    None
+                } else {
+                    decorations
+                        .pressed
+                        .take()
+                        .filter(|pressed| *pressed == decoration_button)
+                }
+            }
+            _ => {
+                decorations.pressed = None;
+                None
+            }
+        };
+        // window menu position is relative to the top left corner of the decorations
+        let menu_x = decorations.pointer_x + decorations::Decorations::WIDTH;
+        let menu_y = decorations.pointer_y + decorations::Decorations::BAR_HEIGHT;
+
+        match (button, area, pressed) {
+            (BTN_LEFT, Some(decorations::Area::Move), true) => self.start_window_move(),
+            (BTN_LEFT, Some(decorations::Area::Resize(edge)), true) => {
+                self.start_window_resize(edge)
+            }
+            (BTN_RIGHT, Some(decorations::Area::Move), true) if !self.seat.is_null() => {
+                wl_request!(
+                    self.client,
+                    self.xdg_toplevel,
+                    xdg_toplevel::show_window_menu,
+                    self.seat,
+                    self.input_serial,
+                    menu_x,
+                    menu_y
+                );
+            }
+            _ => {}
+        }
+
+        match clicked {
+            Some(decorations::Button::Close) => self.closed = true,
+            Some(decorations::Button::Maximize) => self.set_maximized(!self.maximized),
+            Some(decorations::Button::Minimize) => self.minimize(),
+            None => {}
+        }
+    }
+
+    /// Takes the window size including decorations,
+    /// returns the new framebuffer size if it did change
+    unsafe fn resize(&mut self, width: i32, height: i32) -> Option<(i32, i32)> {
+        self.logical_size = if self.decorations.is_some() {
+            // Otherwise window will resize iteself on sway
+            // I have no idea why
+            (
+                width - decorations::Decorations::WIDTH * 2,
+                height - decorations::Decorations::BAR_HEIGHT - decorations::Decorations::WIDTH,
+            )
+        } else {
+            (width, height)
+        };
+
+        if let Some(ref mut decorations) = self.decorations {
+            let (width, height) = self.logical_size;
+            decorations.resize(&mut self.client, self.shm, width, height);
+        }
+
+        self.update_framebuffer()
+    }
+
+    /// Sizes the EGL window for the current logical size and scale.
+    /// The framebuffer is in physical pixels with high_dpi, logical ones otherwise
+    /// (and the compositor upscales it).
+    unsafe fn update_framebuffer(&mut self) -> Option<(i32, i32)> {
+        let (width, height) = self.logical_size;
+        let scale = if self.data.high_dpi {
+            self.scaling.scale()
+        } else {
+            1.
+        };
+        self.data.dpi_scale = scale;
+        if self.data.high_dpi {
+            scale::set_surface_scale(self, scale, width, height);
+        }
+
+        let framebuffer_width = (width as f32 * scale).round() as i32;
+        let framebuffer_height = (height as f32 * scale).round() as i32;
+        (self.egl.wl_egl_window_resize)(
+            self.egl_window,
+            framebuffer_width,
+            framebuffer_height,
+            0,
+            0,
+        );
+
+        // configure is only acked with the next frame, even without a new size
+        self.data.update_requested = true;
+
+        let size = (framebuffer_width, framebuffer_height);
+        if size == (self.data.screen_width, self.data.screen_height) {
+            return None;
+        }
+        self.data.screen_width = framebuffer_width;
+        self.data.screen_height = framebuffer_height;
+        Some(size)
+    }
+
+    /// Pins the window size, used for non-resizable windows
+    unsafe fn set_size_limits(&mut self, width: i32, height: i32) {
+        wl_request!(
+            self.client,
+            self.xdg_toplevel,
+            extensions::xdg_shell::xdg_toplevel::set_min_size,
+            width,
+            height
+        );
+        wl_request!(
+            self.client,
+            self.xdg_toplevel,
+            extensions::xdg_shell::xdg_toplevel::set_max_size,
+            width,
+            height
+        );
+    }
+}
+
+/// Wayland leaves key repeat entirely to the client,
+/// the compositor only tells the rate and delay through wl_keyboard.repeat_info
+struct KeyRepeat {
+    /// Repeats per second, 0 disables key repeat
+    rate: i32,
+    /// Milliseconds before the first repeat
+    delay: i32,
+    /// xkb keycode of the key being held and the time of its next repeat
+    key: Option<(xkb_keycode_t, Instant)>,
+}
+
+impl KeyRepeat {
+    fn start(&mut self, keycode: xkb_keycode_t) {
+        self.key = if self.rate > 0 {
+            Some((
+                keycode,
+                Instant::now() + Duration::from_millis(self.delay as _),
+            ))
+        } else {
+            None
+        };
+    }
+
+    fn stop(&mut self, keycode: xkb_keycode_t) {
+        if matches!(self.key, Some((key, _)) if key == keycode) {
+            self.key = None;
+        }
+    }
+
+    /// When the next repeat is due, if any key is held
+    fn deadline(&self) -> Option<Instant> {
+        self.key.map(|(_, next)| next)
+    }
+
+    /// Returns the held key if its repeat is due
+    fn tick(&mut self) -> Option<xkb_keycode_t> {
+        let (keycode, next) = self.key?;
+        if Instant::now() < next {
+            return None;
+        }
+        self.key = Some((keycode, next + Duration::from_secs(1) / self.rate as u32));
+        Some(keycode)
+    }
+}
+
+impl WaylandDisplay {
+    unsafe fn translate_key(&mut self, keycode: xkb_keycode_t) -> KeyCode {
+        if self.xkb_keymap.is_null() {
+            return KeyCode::Unknown;
+        }
+
+        // Same as on X11: KeyCode is decided by the unshifted keysym
+        let layout = (self.xkb.xkb_state_key_get_layout)(self.xkb_state, keycode);
+        let mut syms: *const xkb_keysym_t = std::ptr::null();
+        let count = (self.xkb.xkb_keymap_key_get_syms_by_level)(
+            self.xkb_keymap,
+            keycode,
+            layout,
+            0,
+            &mut syms,
+        );
+        if count < 1 {
+            return KeyCode::Unknown;
+        }
+        keycodes::translate_keysym(*syms as _)
+    }
+
+    unsafe fn keymods(&mut self) -> KeyMods {
+        if self.xkb_state.is_null() {
+            return KeyMods::default();
+        }
+
+        let is_active = |name: &[u8]| {
+            (self.xkb.xkb_state_mod_name_is_active)(
+                self.xkb_state,
+                name.as_ptr() as _,
+                XKB_STATE_MODS_EFFECTIVE,
+            ) == 1
+        };
+        KeyMods {
+            shift: is_active(XKB_MOD_NAME_SHIFT),
+            ctrl: is_active(XKB_MOD_NAME_CTRL),
+            alt: is_active(XKB_MOD_NAME_ALT),
+            logo: is_active(XKB_MOD_NAME_LOGO),
+        }
+    }
+
+    unsafe fn key_char(&mut self, keycode: xkb_keycode_t) -> Option<char> {
+        if self.xkb_state.is_null() {
+            return None;
+        }
+
+        let chr = (self.xkb.xkb_state_key_get_utf32)(self.xkb_state, keycode);
+        std::char::from_u32(chr).filter(|chr| !chr.is_control())
+    }
+}
+
 pub mod tl_display {
     use super::*;
     use crate::NATIVE_DISPLAY;
@@ -112,6 +670,12 @@ pub mod tl_display {
         DISPLAY.with(|d| *d.borrow_mut() = Some(display));
         NATIVE_DISPLAY.with(|d| *d.borrow_mut() = Some(with_native_display));
     }
+
+    /// Forgets the display once the window is gone, `window::*` functions can't reach it anymore
+    pub fn clear_display() {
+        NATIVE_DISPLAY.with(|d| *d.borrow_mut() = None);
+        DISPLAY.with(|d| *d.borrow_mut() = None);
+    }
 }
 
 /// A thing to pass around within *void pointer of wayland's event handler
@@ -119,61 +683,486 @@ struct WaylandPayload {
     ctx: Option<Box<dyn EventHandler>>,
     client: LibWaylandClient,
     surface: *mut wl_surface,
+    /// Set when input was handed to the app, pump_events turns it into an update request
+    input_dispatched: bool,
 }
 
-#[macro_export]
-macro_rules! wl_request_constructor {
-    ($libwayland:expr, $instance:expr, $request_name:expr, $interface:expr) => {
-        wl_request_constructor!($libwayland, $instance, $request_name, $interface, ())
-    };
+impl WaylandPayload {
+    /// The event handler, for delivering input
+    fn input_handler(&mut self) -> Option<&mut Box<dyn EventHandler>> {
+        self.input_dispatched = true;
+        self.ctx.as_mut()
+    }
+}
 
-    ($libwayland:expr, $instance:expr, $request_name:expr, $interface:expr, $($arg:expr),*) => {{
-        let id: *mut wl_proxy;
+static mut SEAT_LISTENER: wl_seat_listener = wl_seat_listener {
+    capabilities: Some(seat_handle_capabilities),
+    name: Some(seat_handle_name),
+};
 
-        id = ($libwayland.wl_proxy_marshal_constructor)(
-            $instance as _,
-            $request_name,
-            $interface as _,
-            std::ptr::null_mut::<std::ffi::c_void>(),
-            $($arg,)*
+static mut POINTER_LISTENER: wl_pointer_listener = wl_pointer_listener {
+    enter: Some(pointer_handle_enter),
+    leave: Some(pointer_handle_leave),
+    motion: Some(pointer_handle_motion),
+    button: Some(pointer_handle_button),
+    axis: Some(pointer_handle_axis),
+    frame: Some(pointer_handle_frame),
+    axis_source: Some(pointer_handle_axis_source),
+    axis_stop: Some(pointer_handle_axis_stop),
+    axis_discrete: Some(pointer_handle_axis_discrete),
+};
+
+static mut KEYBOARD_LISTENER: wl_keyboard_listener = wl_keyboard_listener {
+    keymap: Some(keyboard_handle_keymap),
+    enter: Some(keyboard_handle_enter),
+    leave: Some(keyboard_handle_leave),
+    key: Some(keyboard_handle_key),
+    modifiers: Some(keyboard_handle_modifiers),
+    repeat_info: Some(keyboard_handle_repeat_info),
+};
+
+unsafe extern "C" fn seat_handle_capabilities(
+    data: *mut std::ffi::c_void,
+    seat: *mut wl_seat,
+    caps: wl_seat_capability,
+) {
+    tl_display::with(|display| {
+        let has_pointer = caps & wl_seat_capability_WL_SEAT_CAPABILITY_POINTER != 0;
+        if has_pointer && display.pointer.is_null() {
+            display.pointer = wl_request_constructor!(
+                display.client,
+                seat,
+                WL_SEAT_GET_POINTER,
+                display.client.wl_pointer_interface
+            );
+            (display.client.wl_proxy_add_listener)(
+                display.pointer as _,
+                std::ptr::addr_of!(POINTER_LISTENER) as _,
+                data,
+            );
+            if !display.cursor_shape_manager.is_null() {
+                display.cursor_images.shape_device = wl_request_constructor!(
+                    display.client,
+                    display.cursor_shape_manager,
+                    extensions::cursor_shape::wp_cursor_shape_manager_v1::get_pointer,
+                    std::ptr::addr_of!(
+                        extensions::cursor_shape::wp_cursor_shape_device_v1_interface
+                    ),
+                    display.pointer
+                );
+            }
+            pointer_grab::init_pointer(display, data);
+        } else if !has_pointer && !display.pointer.is_null() {
+            pointer_grab::release_pointer(display);
+            if !display.cursor_images.shape_device.is_null() {
+                wl_request!(
+                    display.client,
+                    display.cursor_images.shape_device,
+                    extensions::cursor_shape::wp_cursor_shape_device_v1::destroy
+                );
+                (display.client.wl_proxy_destroy)(display.cursor_images.shape_device as _);
+                display.cursor_images.shape_device = std::ptr::null_mut();
+            }
+            (display.client.wl_proxy_destroy)(display.pointer as _);
+            display.pointer = std::ptr::null_mut();
+            display.pointer_focus = std::ptr::null_mut();
+        }
+
+        let has_keyboard = caps & wl_seat_capability_WL_SEAT_CAPABILITY_KEYBOARD != 0;
+        if has_keyboard && display.keyboard.is_null() {
+            display.keyboard = wl_request_constructor!(
+                display.client,
+                seat,
+                WL_SEAT_GET_KEYBOARD,
+                display.client.wl_keyboard_interface
+            );
+            (display.client.wl_proxy_add_listener)(
+                display.keyboard as _,
+                std::ptr::addr_of!(KEYBOARD_LISTENER) as _,
+                data,
+            );
+        } else if !has_keyboard && !display.keyboard.is_null() {
+            (display.client.wl_proxy_destroy)(display.keyboard as _);
+            display.keyboard = std::ptr::null_mut();
+            display.key_repeat.key = None;
+        }
+
+        let has_touch = caps & wl_seat_capability_WL_SEAT_CAPABILITY_TOUCH != 0;
+        touch::update_capability(display, seat, has_touch, data);
+    });
+}
+
+extern "C" fn seat_handle_name(
+    _data: *mut std::ffi::c_void,
+    _seat: *mut wl_seat,
+    _name: *const ::std::os::raw::c_char,
+) {
+}
+
+// linux/input-event-codes.h
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+
+/// Surface distance of a single wheel click on most compositors
+const AXIS_STEP: f64 = 10.;
+
+fn translate_mouse_button(button: u32) -> MouseButton {
+    match button {
+        BTN_LEFT => MouseButton::Left,
+        BTN_RIGHT => MouseButton::Right,
+        BTN_MIDDLE => MouseButton::Middle,
+        _ => MouseButton::Unknown,
+    }
+}
+
+unsafe extern "C" fn pointer_handle_enter(
+    data: *mut std::ffi::c_void,
+    pointer: *mut wl_pointer,
+    serial: u32,
+    surface: *mut wl_surface,
+    surface_x: wl_fixed_t,
+    surface_y: wl_fixed_t,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    // the fallback decorations are surfaces of their own, they are not part of the app
+    let entered = tl_display::with(|display| {
+        display.pointer_focus = surface;
+        display.pointer_serial = serial;
+        cursor::update_cursor(display);
+        surface == display.surface
+    });
+    if entered {
+        if let Some(event_handler) = payload.input_handler() {
+            event_handler.mouse_enter_event();
+        }
+    }
+    pointer_handle_motion(data, pointer, 0, surface_x, surface_y);
+}
+
+unsafe extern "C" fn pointer_handle_leave(
+    data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    _serial: u32,
+    surface: *mut wl_surface,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    let left = tl_display::with(|display| {
+        display.pointer_focus = std::ptr::null_mut();
+        if let Some(ref mut decorations) = display.decorations {
+            decorations.pointer_leave();
+        }
+        surface == display.surface
+    });
+    if left {
+        if let Some(event_handler) = payload.input_handler() {
+            event_handler.mouse_leave_event();
+        }
+    }
+}
+
+unsafe extern "C" fn pointer_handle_motion(
+    data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    _time: u32,
+    surface_x: wl_fixed_t,
+    surface_y: wl_fixed_t,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    let motion = tl_display::with(|display| {
+        if display.pointer_focus != display.surface {
+            let focus = display.pointer_focus;
+            let (x, y) = (wl_fixed_to_double(surface_x), wl_fixed_to_double(surface_y));
+            let cursor_changed = match display.decorations {
+                Some(ref mut decorations) => decorations.pointer_motion(focus, x, y),
+                None => false,
+            };
+            if cursor_changed {
+                cursor::update_cursor(display);
+            }
+            return None;
+        }
+        // framebuffer pixels with high_dpi
+        display.mouse_x = wl_fixed_to_double(surface_x) as f32 * display.data.dpi_scale;
+        display.mouse_y = wl_fixed_to_double(surface_y) as f32 * display.data.dpi_scale;
+        Some((display.mouse_x, display.mouse_y))
+    });
+
+    if let Some((x, y)) = motion {
+        if let Some(event_handler) = payload.input_handler() {
+            event_handler.mouse_motion_event(x, y);
+        }
+    }
+}
+
+unsafe extern "C" fn pointer_handle_button(
+    data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    serial: u32,
+    _time: u32,
+    button: u32,
+    state: u32,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    let pressed = state == wl_pointer_button_state_WL_POINTER_BUTTON_STATE_PRESSED;
+    let (over_window, x, y) = tl_display::with(|display| {
+        display.input_serial = serial;
+        let over_window = display.pointer_focus == display.surface;
+        if !over_window {
+            display.decoration_button(button, pressed);
+        }
+        (over_window, display.mouse_x, display.mouse_y)
+    });
+    let button = translate_mouse_button(button);
+    if !over_window || button == MouseButton::Unknown {
+        return;
+    }
+
+    if let Some(event_handler) = payload.input_handler() {
+        if pressed {
+            event_handler.mouse_button_down_event(button, x, y);
+        } else {
+            event_handler.mouse_button_up_event(button, x, y);
+        }
+    }
+}
+
+unsafe extern "C" fn pointer_handle_axis(
+    data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    _time: u32,
+    axis: u32,
+    value: wl_fixed_t,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    if !tl_display::with(|display| display.pointer_focus == display.surface) {
+        return;
+    }
+
+    // Wayland reports scrolled distance, positive is down/right.
+    // X11 reports one button 4-7 click per wheel step with up/left being positive,
+    // so the value is flipped and scaled to wheel steps to match.
+    let delta = (-wl_fixed_to_double(value) / AXIS_STEP) as f32;
+    if let Some(event_handler) = payload.input_handler() {
+        if axis == wl_pointer_axis_WL_POINTER_AXIS_VERTICAL_SCROLL {
+            event_handler.mouse_wheel_event(0., delta);
+        } else {
+            event_handler.mouse_wheel_event(delta, 0.);
+        }
+    }
+}
+
+unsafe extern "C" fn pointer_handle_frame(_data: *mut std::ffi::c_void, _pointer: *mut wl_pointer) {
+}
+
+unsafe extern "C" fn pointer_handle_axis_source(
+    _data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    _axis_source: u32,
+) {
+}
+
+unsafe extern "C" fn pointer_handle_axis_stop(
+    _data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    _time: u32,
+    _axis: u32,
+) {
+}
+
+unsafe extern "C" fn pointer_handle_axis_discrete(
+    _data: *mut std::ffi::c_void,
+    _pointer: *mut wl_pointer,
+    _axis: u32,
+    _discrete: i32,
+) {
+}
+
+unsafe extern "C" fn keyboard_handle_keymap(
+    _data: *mut std::ffi::c_void,
+    _keyboard: *mut wl_keyboard,
+    format: u32,
+    fd: i32,
+    size: u32,
+) {
+    if format != wl_keyboard_keymap_format_WL_KEYBOARD_KEYMAP_FORMAT_XKB_V1 {
+        libc::close(fd);
+        return;
+    }
+
+    let map = libc::mmap(
+        std::ptr::null_mut(),
+        size as _,
+        libc::PROT_READ,
+        libc::MAP_PRIVATE,
+        fd,
+        0,
+    );
+    libc::close(fd);
+    if map == libc::MAP_FAILED {
+        eprintln!("Failed to mmap wayland keymap");
+        return;
+    }
+
+    tl_display::with(|display| {
+        let keymap = (display.xkb.xkb_keymap_new_from_string)(
+            display.xkb_context,
+            map as _,
+            XKB_KEYMAP_FORMAT_TEXT_V1,
+            XKB_KEYMAP_COMPILE_NO_FLAGS,
         );
+        if keymap.is_null() {
+            eprintln!("Failed to compile wayland keymap");
+            return;
+        }
 
-        id as *mut _
-    }};
+        if !display.xkb_state.is_null() {
+            (display.xkb.xkb_state_unref)(display.xkb_state);
+        }
+        if !display.xkb_keymap.is_null() {
+            (display.xkb.xkb_keymap_unref)(display.xkb_keymap);
+        }
+        display.xkb_keymap = keymap;
+        display.xkb_state = (display.xkb.xkb_state_new)(keymap);
+    });
+
+    libc::munmap(map, size as _);
 }
 
-#[macro_export]
-macro_rules! wl_request {
-    ($libwayland:expr, $instance:expr, $request_name:expr) => {
-        wl_request!($libwayland, $instance, $request_name, ())
-    };
+unsafe extern "C" fn keyboard_handle_enter(
+    data: *mut std::ffi::c_void,
+    _keyboard: *mut wl_keyboard,
+    serial: u32,
+    surface: *mut wl_surface,
+    _keys: *mut wl_array,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
 
-    ($libwayland:expr, $instance:expr, $request_name:expr, $($arg:expr),*) => {{
-        ($libwayland.wl_proxy_marshal)(
-            $instance as _,
-            $request_name,
-            $($arg,)*
-        )
-    }};
+    tl_display::with(|display| {
+        display.focused_window = surface;
+        display.input_serial = serial;
+    });
+    if let Some(event_handler) = payload.input_handler() {
+        event_handler.focus_event(true);
+    }
+}
+
+unsafe extern "C" fn keyboard_handle_leave(
+    data: *mut std::ffi::c_void,
+    _keyboard: *mut wl_keyboard,
+    _serial: u32,
+    _surface: *mut wl_surface,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    tl_display::with(|display| {
+        display.focused_window = std::ptr::null_mut();
+        display.key_repeat.key = None;
+    });
+    if let Some(event_handler) = payload.input_handler() {
+        event_handler.focus_event(false);
+    }
+}
+
+unsafe extern "C" fn keyboard_handle_key(
+    data: *mut std::ffi::c_void,
+    _keyboard: *mut wl_keyboard,
+    serial: u32,
+    _time: u32,
+    key: u32,
+    state: u32,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    // wayland sends evdev scancodes, xkb keycodes are offset by 8
+    let keycode = key + 8;
+
+    if state == wl_keyboard_key_state_WL_KEYBOARD_KEY_STATE_PRESSED {
+        tl_display::with(|display| {
+            display.input_serial = serial;
+            if !display.xkb_keymap.is_null()
+                && (display.xkb.xkb_keymap_key_repeats)(display.xkb_keymap, keycode) == 1
+            {
+                display.key_repeat.start(keycode);
+            }
+        });
+        key_press(payload, keycode, false);
+    } else {
+        let (key, mods) = tl_display::with(|display| {
+            display.key_repeat.stop(keycode);
+            (display.translate_key(keycode), display.keymods())
+        });
+        if let Some(event_handler) = payload.input_handler() {
+            event_handler.key_up_event(key, mods);
+        }
+    }
 }
 
-static mut SEAT_LISTENER: wl_seat_listener = wl_seat_listener {
-    capabilities: Some(seat_handle_capabilities),
-    name: Some(seat_handle_name),
-};
-
-unsafe extern "C" fn seat_handle_capabilities(
+unsafe extern "C" fn keyboard_handle_modifiers(
     _data: *mut std::ffi::c_void,
-    _seat: *mut wl_seat,
-    _caps: wl_seat_capability,
+    _keyboard: *mut wl_keyboard,
+    _serial: u32,
+    mods_depressed: u32,
+    mods_latched: u32,
+    mods_locked: u32,
+    group: u32,
 ) {
+    tl_display::with(|display| {
+        if !display.xkb_state.is_null() {
+            (display.xkb.xkb_state_update_mask)(
+                display.xkb_state,
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                0,
+                0,
+                group,
+            );
+        }
+    });
 }
 
-extern "C" fn seat_handle_name(
+unsafe extern "C" fn keyboard_handle_repeat_info(
     _data: *mut std::ffi::c_void,
-    _seat: *mut wl_seat,
-    _name: *const ::std::os::raw::c_char,
+    _keyboard: *mut wl_keyboard,
+    rate: i32,
+    delay: i32,
 ) {
+    tl_display::with(|display| {
+        display.key_repeat.rate = rate;
+        display.key_repeat.delay = delay;
+    });
+}
+
+/// Shared by wl_keyboard.key and the client side key repeat
+unsafe fn key_press(payload: &mut WaylandPayload, keycode: xkb_keycode_t, repeat: bool) {
+    let (key, mods, chr) = tl_display::with(|display| {
+        (
+            display.translate_key(keycode),
+            display.keymods(),
+            display.key_char(keycode),
+        )
+    });
+
+    if let Some(event_handler) = payload.input_handler() {
+        if let Some(chr) = chr {
+            event_handler.char_event(chr, mods, repeat);
+        }
+        event_handler.key_down_event(key, mods, repeat);
+    }
 }
 
 unsafe extern "C" fn registry_add_object(
@@ -183,21 +1172,26 @@ unsafe extern "C" fn registry_add_object(
     interface: *const ::std::os::raw::c_char,
     version: u32,
 ) {
-    assert!(
-        !tl_display::is_display_set(),
-        "registry_add_object assume display was not moved into a thread local yet"
-    );
+    let interface = std::ffi::CStr::from_ptr(interface).to_str().unwrap();
+
+    // Globals announced later on, like hotplugged monitors.
+    // The display already moved into the thread local and `data` is dangling.
+    if tl_display::is_display_set() {
+        if interface == "wl_output" {
+            tl_display::with(|display| scale::bind_output(display, registry, name, version));
+        }
+        return;
+    }
 
     let display: &mut WaylandDisplay = &mut *(data as *mut _);
 
-    let interface = std::ffi::CStr::from_ptr(interface).to_str().unwrap();
     match interface {
         "wl_compositor" => {
             display.compositor = display.client.wl_registry_bind(
                 registry,
                 name,
                 display.client.wl_compositor_interface,
-                1,
+                4.min(version),
             ) as _;
         }
         "wl_subcompositor" => {
@@ -213,10 +1207,10 @@ unsafe extern "C" fn registry_add_object(
                 registry,
                 name,
                 &extensions::xdg_shell::xdg_wm_base_interface,
-                1,
+                6.min(version),
             ) as _;
         }
-        "zxdg_decoration_manager" => {
+        "zxdg_decoration_manager_v1" => {
             display.decoration_manager = display.client.wl_registry_bind(
                 registry,
                 name,
@@ -232,6 +1226,51 @@ unsafe extern "C" fn registry_add_object(
                 1,
             ) as _;
         }
+        "wp_cursor_shape_manager_v1" => {
+            display.cursor_shape_manager = display.client.wl_registry_bind(
+                registry,
+                name,
+                std::ptr::addr_of!(extensions::cursor_shape::wp_cursor_shape_manager_v1_interface),
+                1,
+            ) as _;
+        }
+        "zwp_pointer_constraints_v1" => {
+            display.pointer_constraints = display.client.wl_registry_bind(
+                registry,
+                name,
+                std::ptr::addr_of!(
+                    extensions::pointer_constraints::zwp_pointer_constraints_v1_interface
+                ),
+                1,
+            ) as _;
+        }
+        "zwp_relative_pointer_manager_v1" => {
+            display.relative_pointer_manager = display.client.wl_registry_bind(
+                registry,
+                name,
+                std::ptr::addr_of!(
+                    extensions::relative_pointer::zwp_relative_pointer_manager_v1_interface
+                ),
+                1,
+            ) as _;
+        }
+        "zwp_text_input_manager_v3" => {
+            display.text_input_manager = display.client.wl_registry_bind(
+                registry,
+                name,
+                std::ptr::addr_of!(extensions::text_input::zwp_text_input_manager_v3_interface),
+                1,
+            ) as _;
+        }
+        "wl_data_device_manager" => {
+            display.data_device.version = 3.min(version);
+            display.data_device_manager = display.client.wl_registry_bind(
+                registry,
+                name,
+                display.client.wl_data_device_manager_interface,
+                display.data_device.version,
+            ) as _;
+        }
         "wl_shm" => {
             display.shm =
                 display
@@ -239,19 +1278,21 @@ unsafe extern "C" fn registry_add_object(
                     .wl_registry_bind(registry, name, display.client.wl_shm_interface, 1)
                     as _;
         }
-        "wl_seat" => {
-            let seat_version = 4.min(version);
-            display.seat = display.client.wl_registry_bind(
+        "wp_fractional_scale_manager_v1" => {
+            display.fractional_scale_manager = display.client.wl_registry_bind(
                 registry,
                 name,
-                display.client.wl_seat_interface,
-                seat_version,
+                std::ptr::addr_of!(
+                    extensions::fractional_scale::wp_fractional_scale_manager_v1_interface
+                ),
+                1,
             ) as _;
-            (display.client.wl_proxy_add_listener)(
-                display.seat as _,
-                &SEAT_LISTENER as *const _ as _,
-                data,
-            );
+        }
+        "wl_output" => {
+            scale::bind_output(display, registry, name, version);
+        }
+        "wl_seat" => {
+            display.seat_global = Some((name, 4.min(version)));
         }
 
         _ => {}
@@ -259,10 +1300,78 @@ unsafe extern "C" fn registry_add_object(
 }
 
 unsafe extern "C" fn registry_remove_object(
-    _data: *mut std::ffi::c_void,
+    data: *mut std::ffi::c_void,
     _registry: *mut wl_registry,
-    _name: u32,
+    name: u32,
+) {
+    // Same as in registry_add_object, `data` is dangling once the display is in the thread local
+    if tl_display::is_display_set() {
+        if tl_display::with(|display| scale::remove_output(display, name)) {
+            scale::scale_changed();
+        }
+        return;
+    }
+    let display: &mut WaylandDisplay = &mut *(data as *mut _);
+    scale::remove_output(display, name);
+}
+
+unsafe extern "C" fn xdg_wm_base_handle_ping(
+    _data: *mut std::ffi::c_void,
+    xdg_wm_base: *mut extensions::xdg_shell::xdg_wm_base,
+    serial: u32,
+) {
+    tl_display::with(|display| {
+        wl_request!(
+            display.client,
+            xdg_wm_base,
+            extensions::xdg_shell::xdg_wm_base::pong,
+            serial
+        );
+    });
+}
+
+static mut REGISTRY_LISTENER: wl_registry_listener = wl_registry_listener {
+    global: Some(registry_add_object),
+    global_remove: Some(registry_remove_object),
+};
+
+static mut XDG_SURFACE_LISTENER: extensions::xdg_shell::xdg_surface_listener =
+    extensions::xdg_shell::xdg_surface_listener {
+        configure: Some(xdg_surface_handle_configure),
+    };
+
+static mut XDG_TOPLEVEL_LISTENER: extensions::xdg_shell::xdg_toplevel_listener =
+    extensions::xdg_shell::xdg_toplevel_listener {
+        configure: Some(xdg_toplevel_handle_configure),
+        close: Some(xdg_toplevel_handle_close),
+        configure_bounds: Some(xdg_toplevel_handle_configure_bounds),
+        wm_capabilities: Some(xdg_toplevel_handle_wm_capabilities),
+    };
+
+static mut XDG_WM_BASE_LISTENER: extensions::xdg_shell::xdg_wm_base_listener =
+    extensions::xdg_shell::xdg_wm_base_listener {
+        ping: Some(xdg_wm_base_handle_ping),
+    };
+
+static mut TOPLEVEL_DECORATION_LISTENER:
+    extensions::xdg_decoration::zxdg_toplevel_decoration_v1_listener =
+    extensions::xdg_decoration::zxdg_toplevel_decoration_v1_listener {
+        configure: Some(toplevel_decoration_handle_configure),
+    };
+
+unsafe extern "C" fn toplevel_decoration_handle_configure(
+    _data: *mut std::ffi::c_void,
+    _toplevel_decoration: *mut extensions::xdg_decoration::zxdg_toplevel_decoration_v1,
+    mode: u32,
 ) {
+    tl_display::with(|display| {
+        let fallback = mode
+            == extensions::xdg_decoration::ZXDG_TOPLEVEL_DECORATION_V1_MODE_CLIENT_SIDE
+            && display.decoration_conf != WaylandDecorations::AppDrawn
+            && display.can_draw_decorations;
+        display.fallback_decorations = fallback;
+        display.set_fallback_decorations(fallback && !display.fullscreen);
+    });
 }
 
 unsafe extern "C" fn xdg_surface_handle_configure(
@@ -297,51 +1406,312 @@ unsafe extern "C" fn xdg_toplevel_handle_configure(
     _toplevel: *mut extensions::xdg_shell::xdg_toplevel,
     width: i32,
     height: i32,
-    _states: *mut wl_array,
+    states: *mut wl_array,
 ) {
+    use extensions::xdg_shell::*;
+
     assert!(!data.is_null());
     let payload: &mut WaylandPayload = &mut *(data as *mut _);
 
-    if width != 0 && height != 0 {
-        tl_display::with(|display| {
-            let (egl_w, egl_h) = if display.decorations.is_some() {
-                // Otherwise window will resize iteself on sway
-                // I have no idea why
-                (
-                    width - decorations::Decorations::WIDTH * 2,
-                    height - decorations::Decorations::BAR_HEIGHT - decorations::Decorations::WIDTH,
-                )
-            } else {
-                (width, height)
-            };
-            (display.egl.wl_egl_window_resize)(display.egl_window, egl_w, egl_h, 0, 0);
+    let states = if (*states).size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts((*states).data as *const u32, (*states).size / 4)
+    };
 
-            display.data.screen_width = width;
-            display.data.screen_height = height;
+    let (resized, visibility_changed) = tl_display::with(|display| {
+        // suspended windows are not shown at all, e.g. minimized or on another workspace
+        let visible = !states.contains(&XDG_TOPLEVEL_STATE_SUSPENDED);
+        let visibility_changed = std::mem::replace(&mut display.visible, visible) != visible;
+        display.fullscreen = states.contains(&XDG_TOPLEVEL_STATE_FULLSCREEN);
+        display.maximized = states.contains(&XDG_TOPLEVEL_STATE_MAXIMIZED);
+        display.activated = states.contains(&XDG_TOPLEVEL_STATE_ACTIVATED);
+        if display.activated {
+            display.minimized = false;
+        }
+        display.set_fallback_decorations(display.fallback_decorations && !display.fullscreen);
 
-            if let Some(ref decorations) = display.decorations {
-                decorations.resize(&mut display.client, width, height);
-            }
-        });
-        if let Some(ref mut event_handler) = payload.ctx {
+        // 0x0 leaves the size up to the client, e.g. after leaving fullscreen
+        let (width, height) = if width != 0 && height != 0 {
+            (width, height)
+        } else {
+            display.windowed_size
+        };
+        if !display.fullscreen && !display.maximized {
+            display.windowed_size = (width, height);
+        }
+
+        (display.resize(width, height), visibility_changed)
+    });
+
+    if let Some(ref mut event_handler) = payload.ctx {
+        if let Some((width, height)) = resized {
             event_handler.resize_event(width as _, height as _);
         }
+        if visibility_changed {
+            event_handler.visibility_event(!states.contains(&XDG_TOPLEVEL_STATE_SUSPENDED));
+        }
+    }
+}
+
+unsafe extern "C" fn xdg_toplevel_handle_configure_bounds(
+    _data: *mut std::ffi::c_void,
+    _toplevel: *mut extensions::xdg_shell::xdg_toplevel,
+    _width: i32,
+    _height: i32,
+) {
+}
+
+unsafe extern "C" fn xdg_toplevel_handle_wm_capabilities(
+    _data: *mut std::ffi::c_void,
+    _toplevel: *mut extensions::xdg_shell::xdg_toplevel,
+    _capabilities: *mut wl_array,
+) {
+}
+
+/// Why the wayland backend could not start, or had to stop
+#[derive(Debug)]
+pub enum WaylandError {
+    /// A required shared library could not be loaded
+    LibraryNotFound(&'static str),
+    /// libEGL could not be loaded
+    EglNotFound,
+    /// There is no compositor to connect to, most likely WAYLAND_DISPLAY is not set
+    ConnectionFailed,
+    /// The compositor does not advertise a global the backend can not work without
+    MissingGlobal(&'static str),
+    Egl(egl::EglError),
+    /// The compositor sent a protocol error and closed the connection
+    Protocol {
+        interface: String,
+        object_id: u32,
+        code: u32,
+    },
+    /// The connection broke for some other reason, or there was no shared memory for a buffer
+    Io(std::io::Error),
+}
+
+impl From<WaylandError> for crate::Error {
+    fn from(error: WaylandError) -> crate::Error {
+        match error {
+            WaylandError::EglNotFound => {
+                crate::Error::GlLoadFailed("libEGL.so not found".to_owned())
+            }
+            WaylandError::LibraryNotFound(library) => {
+                crate::Error::NoDisplayServer(format!("{} not found", library))
+            }
+            WaylandError::ConnectionFailed => crate::Error::NoDisplayServer(
+                "could not connect to a Wayland compositor".to_owned(),
+            ),
+            WaylandError::MissingGlobal(global) => crate::Error::NoDisplayServer(format!(
+                "the Wayland compositor does not support {}",
+                global
+            )),
+            WaylandError::Egl(error) => crate::Error::ContextCreationFailed(format!("{:?}", error)),
+            WaylandError::Protocol {
+                interface,
+                object_id,
+                code,
+            } => crate::Error::Protocol(format!(
+                "Wayland protocol error {} on {}@{}",
+                code, interface, object_id
+            )),
+            WaylandError::Io(error) => crate::Error::Protocol(error.to_string()),
+        }
+    }
+}
+
+/// The fatal error of a broken connection, as told by wl_display_get_error
+unsafe fn display_error(client: &LibWaylandClient, wdisplay: *mut wl_display) -> WaylandError {
+    let errno = (client.wl_display_get_error)(wdisplay);
+    if errno != libc::EPROTO {
+        return WaylandError::Io(std::io::Error::from_raw_os_error(errno));
+    }
+
+    let mut interface: *const wl_interface = std::ptr::null();
+    let mut object_id = 0;
+    let code = (client.wl_display_get_protocol_error)(wdisplay, &mut interface, &mut object_id);
+    let interface = if interface.is_null() {
+        "unknown".to_owned()
+    } else {
+        std::ffi::CStr::from_ptr((*interface).name)
+            .to_string_lossy()
+            .into_owned()
+    };
+    WaylandError::Protocol {
+        interface,
+        object_id,
+        code,
+    }
+}
+
+/// The window with its GL context and event handler, driven either by `run`
+/// or by the app itself through `lokinit::EventPump`
+pub struct WaylandWindow {
+    client: LibWaylandClient,
+    wdisplay: *mut wl_display,
+    payload: Box<WaylandPayload>,
+    libegl: egl::LibEgl,
+    egl_display: egl::EGLDisplay,
+    egl_surface: egl::EGLSurface,
+    user_events: Option<Arc<UserEvents>>,
+}
+
+impl WaylandWindow {
+    /// Dispatches the events that arrived, first waiting up to `timeout` for some,
+    /// or forever on None. Returns Ok(false) once the app should quit.
+    pub fn pump_events(&mut self, timeout: Option<Duration>) -> Result<bool, WaylandError> {
+        unsafe {
+            // key repeat and timers end the wait early
+            let until_deadline = tl_display::with(|d| {
+                [d.key_repeat.deadline(), d.timers.next_deadline()]
+                    .iter()
+                    .flatten()
+                    .min()
+                    .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            });
+            let timeout = [timeout, until_deadline].iter().flatten().min().copied();
+            // negative fds are ignored by poll
+            let user_events_fd = self.user_events.as_ref().map_or(-1, |events| events.fd());
+            if !dispatch_events(&self.client, self.wdisplay, user_events_fd, timeout) {
+                return Err(display_error(&self.client, self.wdisplay));
+            }
+
+            let payload = &mut *self.payload;
+            if let (Some(user_events), Some(event_handler)) = (&self.user_events, &mut payload.ctx)
+            {
+                for user_event in user_events.take() {
+                    event_handler.user_event(user_event);
+                }
+            }
+            let now = Instant::now();
+            while let Some(id) = tl_display::with(|d| d.timers.pop_expired(now)) {
+                if let Some(ref mut event_handler) = payload.ctx {
+                    event_handler.timer_event(id);
+                }
+            }
+
+            if let Some((width, height)) = tl_display::with(|d| d.pending_resize.take()) {
+                if let Some(ref mut event_handler) = payload.ctx {
+                    event_handler.resize_event(width as _, height as _);
+                }
+            }
+
+            let minimized = tl_display::with(|d| {
+                (d.minimized != d.minimized_notified).then(|| {
+                    d.minimized_notified = d.minimized;
+                    d.minimized
+                })
+            });
+            if let (Some(minimized), Some(event_handler)) = (minimized, &mut payload.ctx) {
+                if minimized {
+                    event_handler.window_minimized_event();
+                } else {
+                    event_handler.window_restored_event();
+                }
+            }
+
+            if let Some(keycode) = tl_display::with(|d| d.key_repeat.tick()) {
+                key_press(payload, keycode, true);
+            }
+            // input usually changes what is on screen,
+            // so the blocking event loop draws a frame after it
+            if std::mem::take(&mut payload.input_dispatched) {
+                tl_display::with(|d| d.data.update_requested = true);
+            }
+        }
+        Ok(tl_display::with(|d| !d.closed && !d.data.quit_ordered))
+    }
+
+    /// False while waiting for the compositor to ask for the next frame
+    pub fn frame_ready(&self) -> bool {
+        !tl_display::with(|d| d.frame_pending)
+    }
+
+    pub fn swap_buffers(&mut self) {
+        unsafe {
+            // one callback is enough, frames swapped before it came are not paced
+            tl_display::with(|d| {
+                if !d.frame_pending {
+                    request_frame(d)
+                }
+            });
+            (self.libegl.eglSwapBuffers.unwrap())(self.egl_display, self.egl_surface);
+        }
+    }
+
+    pub fn event_handler(&mut self) -> &mut dyn EventHandler {
+        &mut **self.payload.ctx.as_mut().unwrap()
+    }
+}
+
+impl Drop for WaylandWindow {
+    fn drop(&mut self) {
+        // the app may still free its GL objects or call window::* functions while dropped
+        drop(self.payload.ctx.take());
+        if let Some(user_events) = &self.user_events {
+            user_events.close();
+        }
+        unsafe {
+            // also destroys the context and surface
+            if let Some(terminate) = self.libegl.eglTerminate {
+                terminate(self.egl_display);
+            }
+            tl_display::clear_display();
+            // the compositor destroys everything else, the window goes away with it
+            (self.client.wl_display_disconnect)(self.wdisplay);
+        }
+    }
+}
+
+/// Undoes what `create` did so far when it fails halfway
+struct CreateGuard {
+    client: LibWaylandClient,
+    wdisplay: *mut wl_display,
+    egl_terminate: egl::PFNEGLTERMINATEPROC,
+    egl_display: egl::EGLDisplay,
+    done: bool,
+}
+
+impl Drop for CreateGuard {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        unsafe {
+            if let (Some(terminate), false) = (self.egl_terminate, self.egl_display.is_null()) {
+                terminate(self.egl_display);
+            }
+            tl_display::clear_display();
+            (self.client.wl_display_disconnect)(self.wdisplay);
+        }
     }
 }
 
-pub fn run<F>(conf: &crate::conf::Conf, f: &mut Option<F>) -> Option<()>
+/// Connects and opens the window
+pub fn create<F>(conf: &crate::conf::Conf, f: &mut Option<F>) -> Result<WaylandWindow, WaylandError>
 where
     F: 'static + FnOnce() -> Box<dyn EventHandler>,
 {
     unsafe {
-        let client = LibWaylandClient::try_load()?;
-        let egl = LibWaylandEgl::try_load()?;
+        let mut client = LibWaylandClient::try_load()
+            .ok_or(WaylandError::LibraryNotFound("libwayland-client.so"))?;
+        let egl =
+            LibWaylandEgl::try_load().ok_or(WaylandError::LibraryNotFound("libwayland-egl.so"))?;
+        let xkb =
+            LibXkbCommon::try_load().ok_or(WaylandError::LibraryNotFound("libxkbcommon.so"))?;
 
         let wdisplay = (client.wl_display_connect)(std::ptr::null_mut());
         if wdisplay.is_null() {
-            eprintln!("Failed to connect to Wayland display.");
-            return None;
+            return Err(WaylandError::ConnectionFailed);
         }
+        let mut guard = CreateGuard {
+            client: client.clone(),
+            wdisplay,
+            egl_terminate: None,
+            egl_display: std::ptr::null_mut(),
+            done: false,
+        };
 
         let registry: *mut wl_proxy = wl_request_constructor!(
             client,
@@ -351,13 +1721,9 @@ where
         );
         assert!(!registry.is_null());
 
-        let registry_listener = wl_registry_listener {
-            global: Some(registry_add_object),
-            global_remove: Some(registry_remove_object),
-        };
-
         let mut display = WaylandDisplay {
             client: client.clone(),
+            wdisplay,
             egl,
             compositor: std::ptr::null_mut(),
             subcompositor: std::ptr::null_mut(),
@@ -366,41 +1732,119 @@ where
             surface: std::ptr::null_mut(),
             decoration_manager: std::ptr::null_mut(),
             viewporter: std::ptr::null_mut(),
+            cursor_shape_manager: std::ptr::null_mut(),
+            pointer_constraints: std::ptr::null_mut(),
+            relative_pointer_manager: std::ptr::null_mut(),
+            text_input_manager: std::ptr::null_mut(),
+            fractional_scale_manager: std::ptr::null_mut(),
             shm: std::ptr::null_mut(),
+            data_device_manager: std::ptr::null_mut(),
             seat: std::ptr::null_mut(),
+            seat_global: None,
             egl_window: std::ptr::null_mut(),
             pointer: std::ptr::null_mut(),
             keyboard: std::ptr::null_mut(),
             focused_window: std::ptr::null_mut(),
-            //xkb_state: xkb::XkbState::new(),
+            pointer_focus: std::ptr::null_mut(),
+            pointer_serial: 0,
+            input_serial: 0,
+            mouse_x: 0.,
+            mouse_y: 0.,
+            cursor_icon: CursorIcon::Default,
+            cursor_visible: true,
+            cursor_images: cursor::CursorImages::new(),
+            pointer_grab: pointer_grab::PointerGrab::new(),
+            touch: touch::Touch::new(),
+            text_input: text_input::TextInput::new(),
+            title: String::new(),
+            dropped_files: vec![],
+            user_events: UserEvents::new(),
+            timers: timers::Timers::new(),
+            xkb_context: (xkb.xkb_context_new)(XKB_CONTEXT_NO_FLAGS),
+            xkb,
+            xkb_keymap: std::ptr::null_mut(),
+            xkb_state: std::ptr::null_mut(),
+            key_repeat: KeyRepeat {
+                rate: 25,
+                delay: 600,
+                key: None,
+            },
+            data_device: data_device::DataDevice::new(),
             decorations: None,
+            fallback_decorations: false,
+            decoration_conf: conf.platform.wayland_decorations,
+            can_draw_decorations: false,
+            scaling: scale::Scaling::new(),
+            logical_size: (conf.window_width, conf.window_height),
+            fullscreen: false,
+            maximized: false,
+            activated: false,
+            visible: true,
+            minimized: false,
+            minimized_notified: false,
+            resizable: conf.window_resizable,
+            windowed_size: (conf.window_width, conf.window_height),
+            pending_resize: None,
+            frame_pending: false,
             closed: false,
-            data: Default::default(),
+            data: NativeDisplayData {
+                high_dpi: conf.high_dpi,
+                blocking_event_loop: conf.platform.blocking_event_loop,
+                ..Default::default()
+            },
             get_procaddr: None,
         };
         (display.client.wl_proxy_add_listener)(
             registry,
-            &registry_listener as *const _ as _,
+            std::ptr::addr_of!(REGISTRY_LISTENER) as _,
             &mut display as *mut _ as _,
         );
-        (display.client.wl_display_roundtrip)(wdisplay);
+        if (display.client.wl_display_roundtrip)(wdisplay) < 0 {
+            return Err(display_error(&client, wdisplay));
+        }
+
+        if display.compositor.is_null() {
+            return Err(WaylandError::MissingGlobal("wl_compositor"));
+        }
+        if display.xdg_wm_base.is_null() {
+            return Err(WaylandError::MissingGlobal("xdg_wm_base"));
+        }
+        if display.seat_global.is_none() {
+            eprintln!("Seat not found, there will be no input");
+        }
+        if display.shm.is_null() {
+            eprintln!("wl_shm not found, there will be no fallback cursor");
+        }
 
-        assert!(!display.compositor.is_null());
-        assert!(!display.xdg_wm_base.is_null());
-        assert!(!display.subcompositor.is_null());
-        assert!(!display.seat.is_null());
+        (display.client.wl_proxy_add_listener)(
+            display.xdg_wm_base as _,
+            std::ptr::addr_of!(XDG_WM_BASE_LISTENER) as _,
+            std::ptr::null_mut(),
+        );
 
-        if display.decoration_manager.is_null() {
-            eprintln!("Decoration manager not found, will draw fallback decorations");
+        // fallback decorations are drawn with subsurfaces scaled with viewports out of 1px buffer
+        display.can_draw_decorations = !display.subcompositor.is_null()
+            && !display.viewporter.is_null()
+            && !display.shm.is_null();
+        if display.decoration_manager.is_null()
+            && conf.platform.wayland_decorations == WaylandDecorations::ServerWithFallback
+        {
+            if display.can_draw_decorations {
+                eprintln!("Decoration manager not found, will draw fallback decorations");
+            } else {
+                eprintln!("Decoration manager not found, the window will have no decorations");
+            }
         }
 
-        let mut libegl = egl::LibEgl::try_load()?;
+        let mut libegl = egl::LibEgl::try_load().ok_or(WaylandError::EglNotFound)?;
         let (context, config, egl_display) = egl::create_egl_context(
             &mut libegl,
             wdisplay as *mut _,
             conf.platform.framebuffer_alpha,
         )
-        .unwrap();
+        .map_err(WaylandError::Egl)?;
+        guard.egl_terminate = libegl.eglTerminate;
+        guard.egl_display = egl_display;
 
         display.surface = wl_request_constructor!(
             display.client,
@@ -409,6 +1853,12 @@ where
             display.client.wl_surface_interface
         );
         assert!(!display.surface.is_null());
+        (display.client.wl_proxy_add_listener)(
+            display.surface as _,
+            std::ptr::addr_of!(scale::SURFACE_LISTENER) as _,
+            std::ptr::null_mut(),
+        );
+        scale::init_fractional_scale(&mut display);
 
         let xdg_surface: *mut extensions::xdg_shell::xdg_surface = wl_request_constructor!(
             display.client,
@@ -419,20 +1869,18 @@ where
         );
         assert!(!xdg_surface.is_null());
 
-        let xdg_surface_listener = extensions::xdg_shell::xdg_surface_listener {
-            configure: Some(xdg_surface_handle_configure),
-        };
-
-        let mut payload = WaylandPayload {
+        // listeners get it as their data, so it stays in place on the heap
+        let mut payload = Box::new(WaylandPayload {
             ctx: None,
             client: client.clone(),
             surface: display.surface,
-        };
+            input_dispatched: false,
+        });
 
         (display.client.wl_proxy_add_listener)(
             xdg_surface as _,
-            &xdg_surface_listener as *const _ as _,
-            &mut payload as *mut _ as _,
+            std::ptr::addr_of!(XDG_SURFACE_LISTENER) as _,
+            &mut *payload as *mut _ as _,
         );
 
         display.xdg_toplevel = wl_request_constructor!(
@@ -443,19 +1891,30 @@ where
         );
         assert!(!display.xdg_toplevel.is_null());
 
-        let xdg_toplevel_listener = extensions::xdg_shell::xdg_toplevel_listener {
-            configure: Some(xdg_toplevel_handle_configure),
-            close: Some(xdg_toplevel_handle_close),
-        };
-
         (display.client.wl_proxy_add_listener)(
             display.xdg_toplevel as _,
-            &xdg_toplevel_listener as *const _ as _,
-            &mut payload as *mut _ as _,
+            std::ptr::addr_of!(XDG_TOPLEVEL_LISTENER) as _,
+            &mut *payload as *mut _ as _,
         );
 
+        crate::native::NativeDisplay::set_window_title(&mut display, &conf.window_title);
+        if !conf.app_id.is_empty() {
+            // A C string ends at the first NUL, so does the app_id
+            let app_id = std::ffi::CString::new(conf.app_id.split('\0').next().unwrap()).unwrap();
+            wl_request!(
+                display.client,
+                display.xdg_toplevel,
+                extensions::xdg_shell::xdg_toplevel::set_app_id,
+                app_id.as_ptr()
+            );
+        }
+        if conf.fullscreen {
+            crate::native::NativeDisplay::set_fullscreen(&mut display, true);
+        }
+
+        // the initial configure is handled in the roundtrip after the display
+        // got into the thread local, nothing is drawn before that
         wl_request!(display.client, display.surface, WL_SURFACE_COMMIT);
-        (display.client.wl_display_roundtrip)(wdisplay);
 
         display.egl_window = (display.egl.wl_egl_window_create)(
             display.surface as _,
@@ -472,10 +1931,16 @@ where
 
         if egl_surface.is_null() {
             // EGL_NO_SURFACE
-            panic!("surface creation failed");
+            return Err(WaylandError::Egl(egl::EglError::CreateSurfaceFailed));
         }
         if (libegl.eglMakeCurrent.unwrap())(egl_display, egl_surface, egl_surface, context) == 0 {
-            panic!("eglMakeCurrent failed");
+            return Err(WaylandError::Egl(egl::EglError::MakeCurrentFailed));
+        }
+        // Frames are paced by wl_surface.frame callbacks instead.
+        // With a non-zero interval eglSwapBuffers would wait for the compositor itself,
+        // forever if the window is hidden.
+        if let Some(swap_interval) = libegl.eglSwapInterval {
+            swap_interval(egl_display, 0);
         }
 
         crate::native::gl::load_gl_funcs(|proc| {
@@ -492,47 +1957,228 @@ where
         display.get_procaddr = Some(get_procaddr);
 
         if !display.decoration_manager.is_null() {
-            let server_decoration: *mut extensions::xdg_decoration::zxdg_toplevel_decoration_v1 = wl_request_constructor!(
+            let toplevel_decoration: *mut extensions::xdg_decoration::zxdg_toplevel_decoration_v1 = wl_request_constructor!(
                 display.client,
                 display.decoration_manager,
                 extensions::xdg_decoration::zxdg_decoration_manager_v1::get_toplevel_decoration,
                 &extensions::xdg_decoration::zxdg_toplevel_decoration_v1_interface,
                 display.xdg_toplevel
             );
-            assert!(!server_decoration.is_null());
+            assert!(!toplevel_decoration.is_null());
+            (display.client.wl_proxy_add_listener)(
+                toplevel_decoration as _,
+                std::ptr::addr_of!(TOPLEVEL_DECORATION_LISTENER) as _,
+                std::ptr::null_mut(),
+            );
 
+            // The compositor answers with a configure, and that is what decides
+            let mode = match conf.platform.wayland_decorations {
+                WaylandDecorations::ServerWithFallback => {
+                    extensions::xdg_decoration::ZXDG_TOPLEVEL_DECORATION_V1_MODE_SERVER_SIDE
+                }
+                WaylandDecorations::ClientSide | WaylandDecorations::AppDrawn => {
+                    extensions::xdg_decoration::ZXDG_TOPLEVEL_DECORATION_V1_MODE_CLIENT_SIDE
+                }
+            };
             wl_request!(
                 display.client,
-                server_decoration,
+                toplevel_decoration,
                 extensions::xdg_decoration::zxdg_toplevel_decoration_v1::set_mode,
-                extensions::xdg_decoration::ZXDG_TOPLEVEL_DECORATION_V1_MODE_SERVER_SIDE
+                mode
             );
-        } else {
-            display.decorations = Some(decorations::Decorations::new(
-                &mut display,
-                conf.window_width,
-                conf.window_height,
-            ));
+        } else if display.can_draw_decorations
+            && conf.platform.wayland_decorations != WaylandDecorations::AppDrawn
+        {
+            display.fallback_decorations = true;
+            display.decorations = Some(
+                decorations::Decorations::new(&mut display, conf.window_width, conf.window_height)
+                    .map_err(WaylandError::Io)?,
+            );
+        }
+        let (width, height) = display.outer_size(conf.window_width, conf.window_height);
+        display.windowed_size = (width, height);
+        if !conf.window_resizable {
+            display.set_size_limits(width, height);
         }
+        display.logical_size = (conf.window_width, conf.window_height);
         display.data.screen_width = conf.window_width;
         display.data.screen_height = conf.window_height;
 
+        let seat_global = display.seat_global;
+
         tl_display::set_display(display);
 
+        if let Some((seat_name, seat_version)) = seat_global {
+            let seat: *mut wl_seat = client.wl_registry_bind(
+                registry as _,
+                seat_name,
+                client.wl_seat_interface,
+                seat_version,
+            ) as _;
+            (client.wl_proxy_add_listener)(
+                seat as _,
+                std::ptr::addr_of!(SEAT_LISTENER) as _,
+                &mut *payload as *mut _ as _,
+            );
+            tl_display::with(|d| {
+                d.seat = seat;
+                text_input::init(d, seat, &mut *payload as *mut _ as _);
+                if d.data_device_manager.is_null() {
+                    eprintln!(
+                        "Data device manager not found, clipboard and drag and drop will not work"
+                    );
+                    return;
+                }
+                d.data_device.data_device = wl_request_constructor!(
+                    d.client,
+                    d.data_device_manager,
+                    WL_DATA_DEVICE_MANAGER_GET_DATA_DEVICE,
+                    d.client.wl_data_device_interface,
+                    seat
+                );
+                (d.client.wl_proxy_add_listener)(
+                    d.data_device.data_device as _,
+                    std::ptr::addr_of!(data_device::DATA_DEVICE_LISTENER) as _,
+                    &mut *payload as *mut _ as _,
+                );
+            });
+        }
+        if (client.wl_display_roundtrip)(wdisplay) < 0 {
+            return Err(display_error(&client, wdisplay));
+        }
+
         let event_handler = (f.take().unwrap())();
         payload.ctx = Some(event_handler);
+        guard.done = true;
 
-        while tl_display::with(|d| !d.closed) {
-            (client.wl_display_dispatch_pending)(wdisplay);
+        Ok(WaylandWindow {
+            client,
+            wdisplay,
+            payload,
+            libegl,
+            egl_display,
+            egl_surface,
+            user_events: tl_display::with(|d| d.user_events.clone()),
+        })
+    }
+}
 
-            if let Some(ref mut event_handler) = payload.ctx {
-                event_handler.update();
-                event_handler.draw();
+/// Runs the event loop until the window is closed or the app quits
+pub fn run(window: &mut WaylandWindow) -> Result<(), WaylandError> {
+    loop {
+        // Sleep until the compositor wants a new frame, some input arrives or a timer is due
+        let timeout = tl_display::with(|d| {
+            if !d.frame_pending && (!d.data.blocking_event_loop || d.data.update_requested) {
+                Some(Duration::ZERO)
+            } else {
+                None
             }
+        });
+        if !window.pump_events(timeout)? {
+            break;
+        }
+
+        if tl_display::with(|d| d.frame_pending || !d.data.take_update_request()) {
+            continue;
+        }
+
+        let event_handler = window.event_handler();
+        event_handler.update();
+        event_handler.draw();
+        window.swap_buffers();
+    }
+
+    Ok(())
+}
+
+/// Reads and dispatches events, waiting up to `timeout` for them to arrive, or forever on `None`.
+/// `wakeup_fd` becoming readable also ends the wait.
+/// Returns false once the connection is gone.
+unsafe fn dispatch_events(
+    client: &LibWaylandClient,
+    wdisplay: *mut wl_display,
+    wakeup_fd: libc::c_int,
+    timeout: Option<Duration>,
+) -> bool {
+    // Only the thread that got prepare_read through may read,
+    // everything already queued has to be dispatched first
+    while (client.wl_display_prepare_read)(wdisplay) != 0 {
+        if (client.wl_display_dispatch_pending)(wdisplay) < 0 {
+            return false;
+        }
+    }
+    // Requests sent by the app and the last frame are only buffered until now.
+    // On a full socket the rest is sent once the compositor made room
+    let mut events = libc::POLLIN;
+    if (client.wl_display_flush)(wdisplay) < 0 {
+        if std::io::Error::last_os_error().raw_os_error() != Some(libc::EAGAIN) {
+            (client.wl_display_cancel_read)(wdisplay);
+            return false;
+        }
+        events |= libc::POLLOUT;
+    }
 
-            (libegl.eglSwapBuffers.unwrap())(egl_display, egl_surface);
+    let mut pollfds = [
+        libc::pollfd {
+            fd: (client.wl_display_get_fd)(wdisplay),
+            events,
+            revents: 0,
+        },
+        libc::pollfd {
+            fd: wakeup_fd,
+            events: libc::POLLIN,
+            revents: 0,
+        },
+    ];
+    libc::poll(
+        pollfds.as_mut_ptr(),
+        pollfds.len() as _,
+        timers::poll_timeout(timeout),
+    );
+    if pollfds[0].revents & libc::POLLOUT != 0 {
+        (client.wl_display_flush)(wdisplay);
+    }
+    // errors and hangups as well, read_events reports them
+    if pollfds[0].revents & !libc::POLLOUT != 0 {
+        if (client.wl_display_read_events)(wdisplay) < 0 {
+            return false;
         }
+    } else {
+        (client.wl_display_cancel_read)(wdisplay);
     }
 
-    Some(())
+    (client.wl_display_dispatch_pending)(wdisplay) >= 0
+}
+
+static mut FRAME_LISTENER: wl_callback_listener = wl_callback_listener {
+    done: Some(frame_handle_done),
+};
+
+/// Asks the compositor to tell when it is a good time to draw the next frame.
+/// The callback is only sent once the frame is actually shown,
+/// so minimized and hidden windows stop drawing.
+unsafe fn request_frame(display: &mut WaylandDisplay) {
+    let callback: *mut wl_callback = wl_request_constructor!(
+        display.client,
+        display.surface,
+        WL_SURFACE_FRAME,
+        display.client.wl_callback_interface
+    );
+    (display.client.wl_proxy_add_listener)(
+        callback as _,
+        std::ptr::addr_of!(FRAME_LISTENER) as _,
+        std::ptr::null_mut(),
+    );
+    display.frame_pending = true;
+}
+
+unsafe extern "C" fn frame_handle_done(
+    _data: *mut std::ffi::c_void,
+    callback: *mut wl_callback,
+    _time: u32,
+) {
+    tl_display::with(|display| {
+        display.frame_pending = false;
+        (display.client.wl_proxy_destroy)(callback as _);
+    });
 }
@@ -0,0 +1,247 @@
+//! Mouse cursor images.
+//! Wayland compositors do not draw a cursor for us, the client attaches it to the pointer
+//! on every wl_pointer.enter. When the compositor supports wp_cursor_shape_v1 it picks
+//! the image itself, otherwise the cursor theme is loaded through libwayland-cursor,
+//! and if even that is not available - a plain arrow is drawn into a wl_shm buffer.
+
+use super::{
+    extensions::cursor_shape::*,
+    libwayland_client::*,
+    libwayland_cursor::{wl_cursor_theme, LibWaylandCursor},
+    shm, WaylandDisplay,
+};
+use crate::{wl_request, wl_request_constructor, CursorIcon};
+
+pub(crate) struct CursorImages {
+    libcursor: Option<LibWaylandCursor>,
+    theme: *mut wl_cursor_theme,
+    surface: *mut wl_surface,
+    fallback: *mut wl_buffer,
+    pub shape_device: *mut wp_cursor_shape_device_v1,
+}
+
+impl CursorImages {
+    pub fn new() -> CursorImages {
+        let libcursor = LibWaylandCursor::try_load();
+        if libcursor.is_none() {
+            eprintln!("libwayland-cursor not found, will draw fallback cursor");
+        }
+
+        CursorImages {
+            libcursor,
+            theme: std::ptr::null_mut(),
+            surface: std::ptr::null_mut(),
+            fallback: std::ptr::null_mut(),
+            shape_device: std::ptr::null_mut(),
+        }
+    }
+}
+
+const FALLBACK_SIZE: i32 = 16;
+
+/// Black arrow with a white outline, BGRA as wl_shm's ARGB8888 is little endian
+fn fallback_arrow() -> Vec<u8> {
+    let inside = |x: i32, y: i32| {
+        (0..FALLBACK_SIZE).contains(&x)
+            && (0..FALLBACK_SIZE).contains(&y)
+            && x <= y
+            && x + y / 2 < 12
+    };
+
+    let mut pixels = Vec::with_capacity((FALLBACK_SIZE * FALLBACK_SIZE * 4) as usize);
+    for y in 0..FALLBACK_SIZE {
+        for x in 0..FALLBACK_SIZE {
+            let pixel = if !inside(x, y) {
+                [0, 0, 0, 0]
+            } else if inside(x - 1, y) && inside(x + 1, y) && inside(x, y - 1) && inside(x, y + 1) {
+                [0, 0, 0, 255]
+            } else {
+                [255, 255, 255, 255]
+            };
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+    pixels
+}
+
+fn shape(cursor_icon: CursorIcon) -> u32 {
+    match cursor_icon {
+        CursorIcon::Default => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_DEFAULT,
+        CursorIcon::Help => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_HELP,
+        CursorIcon::Pointer => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_POINTER,
+        CursorIcon::Wait => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_WAIT,
+        CursorIcon::Crosshair => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_CROSSHAIR,
+        CursorIcon::Text => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_TEXT,
+        CursorIcon::Move => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_MOVE,
+        CursorIcon::NotAllowed => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NOT_ALLOWED,
+        CursorIcon::EWResize => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_EW_RESIZE,
+        CursorIcon::NSResize => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NS_RESIZE,
+        CursorIcon::NESWResize => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NESW_RESIZE,
+        CursorIcon::NWSEResize => WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NWSE_RESIZE,
+    }
+}
+
+/// Cursor themes are not consistent in naming, so there are a few candidates for each icon.
+/// The first one is the freedesktop cursor spec name, the rest are legacy X11 font cursors.
+fn theme_names(cursor_icon: CursorIcon) -> &'static [&'static str] {
+    match cursor_icon {
+        CursorIcon::Default => &["default", "left_ptr"],
+        CursorIcon::Help => &["help", "question_arrow"],
+        CursorIcon::Pointer => &["pointer", "hand2", "hand1"],
+        CursorIcon::Wait => &["wait", "watch"],
+        CursorIcon::Crosshair => &["crosshair", "cross"],
+        CursorIcon::Text => &["text", "xterm"],
+        CursorIcon::Move => &["move", "fleur"],
+        CursorIcon::NotAllowed => &["not-allowed", "crossed_circle"],
+        CursorIcon::EWResize => &["ew-resize", "sb_h_double_arrow"],
+        CursorIcon::NSResize => &["ns-resize", "sb_v_double_arrow"],
+        CursorIcon::NESWResize => &["nesw-resize", "fd_double_arrow", "top_right_corner"],
+        CursorIcon::NWSEResize => &["nwse-resize", "bd_double_arrow", "top_left_corner"],
+    }
+}
+
+/// Returns the buffer and its hotspot
+unsafe fn themed_cursor(
+    display: &mut WaylandDisplay,
+    cursor_icon: CursorIcon,
+) -> Option<(*mut wl_buffer, i32, i32)> {
+    let images = &mut display.cursor_images;
+    let libcursor = images.libcursor.as_ref()?;
+
+    if images.theme.is_null() {
+        let theme = std::env::var("XCURSOR_THEME")
+            .ok()
+            .and_then(|theme| std::ffi::CString::new(theme).ok());
+        let size = std::env::var("XCURSOR_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(24);
+        images.theme = (libcursor.wl_cursor_theme_load)(
+            theme
+                .as_ref()
+                .map_or(std::ptr::null(), |theme| theme.as_ptr()),
+            size,
+            display.shm,
+        );
+        if images.theme.is_null() {
+            return None;
+        }
+    }
+
+    for name in theme_names(cursor_icon) {
+        let name = std::ffi::CString::new(*name).unwrap();
+        let cursor = (libcursor.wl_cursor_theme_get_cursor)(images.theme, name.as_ptr());
+        if cursor.is_null() || (*cursor).image_count == 0 {
+            continue;
+        }
+
+        // animated cursors are not supported, the first frame is used
+        let image = *(*cursor).images;
+        let buffer = (libcursor.wl_cursor_image_get_buffer)(image);
+        if !buffer.is_null() {
+            return Some((buffer, (*image).hotspot_x as _, (*image).hotspot_y as _));
+        }
+    }
+    None
+}
+
+/// Attaches the cursor for the surface under the pointer.
+/// Needs to be called on each wl_pointer.enter, the image is forgotten on leave.
+pub(super) unsafe fn update_cursor(display: &mut WaylandDisplay) {
+    if display.pointer.is_null() || display.pointer_focus.is_null() {
+        return;
+    }
+
+    if !display.cursor_visible {
+        wl_request!(
+            display.client,
+            display.pointer,
+            WL_POINTER_SET_CURSOR,
+            display.pointer_serial,
+            std::ptr::null_mut::<wl_surface>(),
+            0,
+            0
+        );
+        return;
+    }
+
+    // decorations are not the app's business, they get resize arrows over the borders
+    let cursor_icon = if display.pointer_focus == display.surface {
+        display.cursor_icon
+    } else {
+        display
+            .decorations
+            .as_ref()
+            .and_then(|decorations| decorations.hovered)
+            .map_or(CursorIcon::Default, |area| area.cursor_icon())
+    };
+
+    if !display.cursor_images.shape_device.is_null() {
+        wl_request!(
+            display.client,
+            display.cursor_images.shape_device,
+            wp_cursor_shape_device_v1::set_shape,
+            display.pointer_serial,
+            shape(cursor_icon)
+        );
+        return;
+    }
+
+    // both cursor themes and the fallback arrow are shared memory buffers
+    if display.shm.is_null() {
+        return;
+    }
+
+    let (buffer, hotspot_x, hotspot_y) = match themed_cursor(display, cursor_icon) {
+        Some(cursor) => cursor,
+        None => {
+            if display.cursor_images.fallback.is_null() {
+                match shm::create_shm_buffer(
+                    &mut display.client,
+                    display.shm,
+                    FALLBACK_SIZE,
+                    FALLBACK_SIZE,
+                    &fallback_arrow(),
+                ) {
+                    Ok(buffer) => display.cursor_images.fallback = buffer,
+                    Err(error) => {
+                        eprintln!("Failed to draw the fallback cursor: {}", error);
+                        return;
+                    }
+                }
+            }
+            (display.cursor_images.fallback, 0, 0)
+        }
+    };
+
+    if display.cursor_images.surface.is_null() {
+        display.cursor_images.surface = wl_request_constructor!(
+            display.client,
+            display.compositor,
+            WL_COMPOSITOR_CREATE_SURFACE,
+            display.client.wl_surface_interface
+        );
+    }
+    let surface = display.cursor_images.surface;
+
+    wl_request!(
+        display.client,
+        display.pointer,
+        WL_POINTER_SET_CURSOR,
+        display.pointer_serial,
+        surface,
+        hotspot_x,
+        hotspot_y
+    );
+    wl_request!(display.client, surface, WL_SURFACE_ATTACH, buffer, 0, 0);
+    wl_request!(
+        display.client,
+        surface,
+        WL_SURFACE_DAMAGE,
+        0,
+        0,
+        i32::MAX,
+        i32::MAX
+    );
+    wl_request!(display.client, surface, WL_SURFACE_COMMIT);
+}
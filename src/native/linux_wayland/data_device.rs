@@ -0,0 +1,447 @@
+//! wl_data_device based clipboard and file drops.
+//! Every selection (and drag and drop) arrives as a wl_data_offer advertising a set of mime types,
+//! the actual data is requested with wl_data_offer.receive and read from a pipe.
+//! Our own selection is served from a wl_data_source, the compositor asks for it with
+//! wl_data_source.send and a pipe to write into.
+
+use super::{libwayland_client::*, tl_display, WaylandDisplay, WaylandPayload};
+use crate::{wl_request, wl_request_constructor};
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+
+/// Mime types for plain text, in order of preference
+const TEXT_MIME_TYPES: &[&str] = &["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+
+/// Mime type of dropped files
+const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+/// wl_data_device_manager.dnd_action.copy
+const DND_ACTION_COPY: u32 = 1;
+
+/// How long to wait for the selection owner before giving up on reading an offer
+const READ_TIMEOUT_MS: i32 = 1000;
+
+pub(crate) struct DataDevice {
+    pub data_device: *mut wl_data_device,
+    /// Bound version of wl_data_device_manager, drag and drop actions need 3
+    pub version: u32,
+    /// Mime types advertised by each live offer
+    offers: HashMap<*mut wl_data_offer, Vec<String>>,
+    selection: *mut wl_data_offer,
+    /// Offer of the drag currently over the window
+    drag_offer: *mut wl_data_offer,
+    /// Our own selection, if we own the clipboard
+    source: *mut wl_data_source,
+    source_data: String,
+}
+
+impl DataDevice {
+    pub fn new() -> DataDevice {
+        DataDevice {
+            data_device: std::ptr::null_mut(),
+            version: 1,
+            offers: HashMap::new(),
+            selection: std::ptr::null_mut(),
+            drag_offer: std::ptr::null_mut(),
+            source: std::ptr::null_mut(),
+            source_data: String::new(),
+        }
+    }
+}
+
+pub(super) static mut DATA_DEVICE_LISTENER: wl_data_device_listener = wl_data_device_listener {
+    data_offer: Some(data_device_handle_data_offer),
+    enter: Some(data_device_handle_enter),
+    leave: Some(data_device_handle_leave),
+    motion: Some(data_device_handle_motion),
+    drop: Some(data_device_handle_drop),
+    selection: Some(data_device_handle_selection),
+};
+
+static mut DATA_OFFER_LISTENER: wl_data_offer_listener = wl_data_offer_listener {
+    offer: Some(data_offer_handle_offer),
+    source_actions: Some(data_offer_handle_source_actions),
+    action: Some(data_offer_handle_action),
+};
+
+static mut DATA_SOURCE_LISTENER: wl_data_source_listener = wl_data_source_listener {
+    target: Some(data_source_handle_target),
+    send: Some(data_source_handle_send),
+    cancelled: Some(data_source_handle_cancelled),
+    dnd_drop_performed: Some(data_source_handle_dnd_drop_performed),
+    dnd_finished: Some(data_source_handle_dnd_finished),
+    action: Some(data_source_handle_action),
+};
+
+unsafe fn destroy_offer(display: &mut WaylandDisplay, offer: *mut wl_data_offer) {
+    if offer.is_null() {
+        return;
+    }
+    display.data_device.offers.remove(&offer);
+    wl_request!(display.client, offer, WL_DATA_OFFER_DESTROY);
+    (display.client.wl_proxy_destroy)(offer as _);
+}
+
+unsafe extern "C" fn data_device_handle_data_offer(
+    data: *mut std::ffi::c_void,
+    _data_device: *mut wl_data_device,
+    offer: *mut wl_data_offer,
+) {
+    tl_display::with(|display| {
+        display.data_device.offers.insert(offer, vec![]);
+        (display.client.wl_proxy_add_listener)(
+            offer as _,
+            std::ptr::addr_of!(DATA_OFFER_LISTENER) as _,
+            data,
+        );
+    });
+}
+
+fn offers_files(display: &WaylandDisplay, offer: *mut wl_data_offer) -> bool {
+    match display.data_device.offers.get(&offer) {
+        Some(mime_types) => mime_types
+            .iter()
+            .any(|mime_type| mime_type == URI_LIST_MIME_TYPE),
+        None => false,
+    }
+}
+
+unsafe extern "C" fn data_device_handle_enter(
+    _data: *mut std::ffi::c_void,
+    _data_device: *mut wl_data_device,
+    serial: u32,
+    _surface: *mut wl_surface,
+    _x: wl_fixed_t,
+    _y: wl_fixed_t,
+    offer: *mut wl_data_offer,
+) {
+    tl_display::with(|display| {
+        destroy_offer(display, display.data_device.drag_offer);
+        // null when the drag comes from a client-side only drag, e.g. within another app
+        display.data_device.drag_offer = offer;
+        if offer.is_null() {
+            return;
+        }
+
+        // files are the only thing we take
+        if offers_files(display, offer) {
+            let mime_type = CString::new(URI_LIST_MIME_TYPE).unwrap();
+            wl_request!(
+                display.client,
+                offer,
+                WL_DATA_OFFER_ACCEPT,
+                serial,
+                mime_type.as_ptr()
+            );
+            if display.data_device.version >= 3 {
+                wl_request!(
+                    display.client,
+                    offer,
+                    WL_DATA_OFFER_SET_ACTIONS,
+                    DND_ACTION_COPY,
+                    DND_ACTION_COPY
+                );
+            }
+        } else {
+            wl_request!(
+                display.client,
+                offer,
+                WL_DATA_OFFER_ACCEPT,
+                serial,
+                std::ptr::null::<libc::c_char>()
+            );
+        }
+    });
+}
+
+unsafe extern "C" fn data_device_handle_leave(
+    _data: *mut std::ffi::c_void,
+    _data_device: *mut wl_data_device,
+) {
+    tl_display::with(|display| {
+        destroy_offer(display, display.data_device.drag_offer);
+        display.data_device.drag_offer = std::ptr::null_mut();
+    });
+}
+
+unsafe extern "C" fn data_device_handle_motion(
+    _data: *mut std::ffi::c_void,
+    _data_device: *mut wl_data_device,
+    _time: u32,
+    _x: wl_fixed_t,
+    _y: wl_fixed_t,
+) {
+}
+
+unsafe extern "C" fn data_device_handle_drop(
+    data: *mut std::ffi::c_void,
+    _data_device: *mut wl_data_device,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    let dropped = tl_display::with(|display| {
+        let offer = std::mem::replace(&mut display.data_device.drag_offer, std::ptr::null_mut());
+        if offer.is_null() {
+            return false;
+        }
+
+        let paths = if offers_files(display, offer) {
+            receive(display, offer, URI_LIST_MIME_TYPE)
+                .map(|bytes| crate::native::uri_list::parse_file_paths(&bytes))
+                .unwrap_or_default()
+        } else {
+            vec![]
+        };
+        if display.data_device.version >= 3 && !paths.is_empty() {
+            wl_request!(display.client, offer, WL_DATA_OFFER_FINISH);
+        }
+        destroy_offer(display, offer);
+
+        if paths.is_empty() {
+            return false;
+        }
+        display.dropped_files = paths;
+        true
+    });
+
+    if dropped {
+        if let Some(event_handler) = payload.input_handler() {
+            event_handler.files_dropped_event();
+        }
+    }
+}
+
+unsafe extern "C" fn data_device_handle_selection(
+    _data: *mut std::ffi::c_void,
+    _data_device: *mut wl_data_device,
+    offer: *mut wl_data_offer,
+) {
+    tl_display::with(|display| {
+        if display.data_device.selection != offer {
+            destroy_offer(display, display.data_device.selection);
+        }
+        // null when the clipboard got empty
+        display.data_device.selection = offer;
+    });
+}
+
+unsafe extern "C" fn data_offer_handle_offer(
+    _data: *mut std::ffi::c_void,
+    offer: *mut wl_data_offer,
+    mime_type: *const ::std::os::raw::c_char,
+) {
+    let mime_type = CStr::from_ptr(mime_type).to_string_lossy().into_owned();
+    tl_display::with(|display| {
+        if let Some(mime_types) = display.data_device.offers.get_mut(&offer) {
+            mime_types.push(mime_type.clone());
+        }
+    });
+}
+
+unsafe extern "C" fn data_offer_handle_source_actions(
+    _data: *mut std::ffi::c_void,
+    _offer: *mut wl_data_offer,
+    _source_actions: u32,
+) {
+}
+
+unsafe extern "C" fn data_offer_handle_action(
+    _data: *mut std::ffi::c_void,
+    _offer: *mut wl_data_offer,
+    _dnd_action: u32,
+) {
+}
+
+unsafe extern "C" fn data_source_handle_target(
+    _data: *mut std::ffi::c_void,
+    _source: *mut wl_data_source,
+    _mime_type: *const ::std::os::raw::c_char,
+) {
+}
+
+unsafe extern "C" fn data_source_handle_send(
+    _data: *mut std::ffi::c_void,
+    source: *mut wl_data_source,
+    _mime_type: *const ::std::os::raw::c_char,
+    fd: i32,
+) {
+    // all offered mime types are plain text, so the mime type is not important
+    let bytes = tl_display::with(|display| {
+        if display.data_device.source == source {
+            display.data_device.source_data.clone().into_bytes()
+        } else {
+            vec![]
+        }
+    });
+
+    let mut written = 0;
+    while written < bytes.len() {
+        let res = libc::write(fd, bytes[written..].as_ptr() as _, bytes.len() - written);
+        if res <= 0 {
+            break;
+        }
+        written += res as usize;
+    }
+    libc::close(fd);
+}
+
+unsafe extern "C" fn data_source_handle_cancelled(
+    _data: *mut std::ffi::c_void,
+    source: *mut wl_data_source,
+) {
+    tl_display::with(|display| {
+        if display.data_device.source == source {
+            display.data_device.source = std::ptr::null_mut();
+            display.data_device.source_data.clear();
+        }
+        wl_request!(display.client, source, WL_DATA_SOURCE_DESTROY);
+        (display.client.wl_proxy_destroy)(source as _);
+    });
+}
+
+unsafe extern "C" fn data_source_handle_dnd_drop_performed(
+    _data: *mut std::ffi::c_void,
+    _source: *mut wl_data_source,
+) {
+}
+
+unsafe extern "C" fn data_source_handle_dnd_finished(
+    _data: *mut std::ffi::c_void,
+    _source: *mut wl_data_source,
+) {
+}
+
+unsafe extern "C" fn data_source_handle_action(
+    _data: *mut std::ffi::c_void,
+    _source: *mut wl_data_source,
+    _dnd_action: u32,
+) {
+}
+
+/// Asks the offer's owner to write the data in the given mime type into a pipe and reads it
+/// Blocks until the owner closes the pipe, or stays silent for READ_TIMEOUT_MS.
+/// None on a timeout or a read error, the data would be incomplete.
+pub(super) unsafe fn receive(
+    display: &mut WaylandDisplay,
+    offer: *mut wl_data_offer,
+    mime_type: &str,
+) -> Option<Vec<u8>> {
+    let mut fds = [0; 2];
+    if libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) != 0 {
+        return None;
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+
+    let mime_type = CString::new(mime_type).unwrap();
+    wl_request!(
+        display.client,
+        offer,
+        WL_DATA_OFFER_RECEIVE,
+        mime_type.as_ptr(),
+        write_fd
+    );
+    libc::close(write_fd);
+    (display.client.wl_display_flush)(display.wdisplay);
+
+    let interrupted = || std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted;
+    let mut bytes = vec![];
+    let mut buf = [0u8; 4096];
+    let complete = loop {
+        let mut pollfd = libc::pollfd {
+            fd: read_fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match libc::poll(&mut pollfd, 1, READ_TIMEOUT_MS) {
+            0 => {
+                eprintln!("Timed out reading wayland data offer");
+                break false;
+            }
+            res if res < 0 && interrupted() => continue,
+            res if res < 0 => break false,
+            _ => {}
+        }
+        let res = libc::read(read_fd, buf.as_mut_ptr() as _, buf.len());
+        // the owner closed its end, everything is there
+        if res == 0 {
+            break true;
+        }
+        if res < 0 {
+            if interrupted() {
+                continue;
+            }
+            break false;
+        }
+        bytes.extend_from_slice(&buf[..res as usize]);
+    };
+    libc::close(read_fd);
+
+    complete.then_some(bytes)
+}
+
+pub(super) unsafe fn clipboard_get(display: &mut WaylandDisplay) -> Option<String> {
+    // Reading our own selection through a pipe would deadlock:
+    // wl_data_source.send could only be dispatched after the read is over
+    if !display.data_device.source.is_null() {
+        return Some(display.data_device.source_data.clone());
+    }
+
+    let offer = display.data_device.selection;
+    let mime_types = display.data_device.offers.get(&offer)?;
+    let mime_type = TEXT_MIME_TYPES
+        .iter()
+        .find(|mime_type| mime_types.iter().any(|offered| offered == *mime_type))?;
+
+    let bytes = receive(display, offer, mime_type)?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub(super) unsafe fn clipboard_set(display: &mut WaylandDisplay, data: &str) {
+    if display.data_device_manager.is_null() || display.data_device.data_device.is_null() {
+        return;
+    }
+
+    // replacing our own selection, the compositor would cancel the old source anyway
+    if !display.data_device.source.is_null() {
+        wl_request!(
+            display.client,
+            display.data_device.source,
+            WL_DATA_SOURCE_DESTROY
+        );
+        (display.client.wl_proxy_destroy)(display.data_device.source as _);
+    }
+
+    let source: *mut wl_data_source = wl_request_constructor!(
+        display.client,
+        display.data_device_manager,
+        WL_DATA_DEVICE_MANAGER_CREATE_DATA_SOURCE,
+        display.client.wl_data_source_interface
+    );
+    (display.client.wl_proxy_add_listener)(
+        source as _,
+        std::ptr::addr_of!(DATA_SOURCE_LISTENER) as _,
+        std::ptr::null_mut(),
+    );
+    for mime_type in TEXT_MIME_TYPES {
+        let mime_type = CString::new(*mime_type).unwrap();
+        wl_request!(
+            display.client,
+            source,
+            WL_DATA_SOURCE_OFFER,
+            mime_type.as_ptr()
+        );
+    }
+
+    wl_request!(
+        display.client,
+        display.data_device.data_device,
+        WL_DATA_DEVICE_SET_SELECTION,
+        source,
+        display.input_serial
+    );
+
+    display.data_device.source = source;
+    display.data_device.source_data = data.to_owned();
+}
@@ -5,6 +5,10 @@
 //!
 //! So this module is drawing some sort of a window border, just for GNOME
 //! looks horrible, doesn't fit OS theme at all, but better than nothing
+//!
+//! The borders are 1px buffers stretched with viewports, the title bar is drawn
+//! into its own buffer: the title with a tiny built-in font and the
+//! minimize/maximize/close buttons on the right.
 
 use crate::{
     native::linux_wayland::{
@@ -12,21 +16,242 @@ use crate::{
         libwayland_client::*,
         shm, WaylandDisplay,
     },
-    wl_request, wl_request_constructor,
+    wl_request, wl_request_constructor, CursorIcon, ResizeEdge,
 };
 
 pub struct Decoration {
     pub surface: *mut wl_surface,
     pub subsurface: *mut wl_subsurface,
     pub viewport: *mut wp_viewport,
+    /// Position relative to the window content
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Button {
+    Minimize,
+    Maximize,
+    Close,
+}
+
+/// Part of the decorations under the pointer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Area {
+    Move,
+    Resize(ResizeEdge),
+    Button(Button),
+}
+
+impl Area {
+    pub fn cursor_icon(self) -> CursorIcon {
+        match self {
+            Area::Move | Area::Button(_) => CursorIcon::Default,
+            Area::Resize(ResizeEdge::Top | ResizeEdge::Bottom) => CursorIcon::NSResize,
+            Area::Resize(ResizeEdge::Left | ResizeEdge::Right) => CursorIcon::EWResize,
+            Area::Resize(ResizeEdge::TopLeft | ResizeEdge::BottomRight) => CursorIcon::NWSEResize,
+            Area::Resize(ResizeEdge::TopRight | ResizeEdge::BottomLeft) => CursorIcon::NESWResize,
+        }
+    }
 }
 
 pub(crate) struct Decorations {
     buffer: *mut wl_buffer,
+    title_buffer: *mut wl_buffer,
+    title: String,
+    /// Size of the window content
+    width: i32,
+    height: i32,
     pub top_decoration: Decoration,
     pub bottom_decoration: Decoration,
     pub left_decoration: Decoration,
     pub right_decoration: Decoration,
+    /// Area under the pointer
+    pub hovered: Option<Area>,
+    /// Pointer position relative to the window content
+    pub pointer_x: i32,
+    pub pointer_y: i32,
+    /// Button pressed and not yet released, it is clicked on release over the same button
+    pub pressed: Option<Button>,
+}
+
+const BACKGROUND: [u8; 4] = [200, 200, 200, 255];
+const FOREGROUND: [u8; 4] = [40, 40, 40, 255];
+
+/// Each glyph pixel is drawn as a FONT_SCALE x FONT_SCALE square
+const FONT_SCALE: i32 = 2;
+const GLYPH_WIDTH: i32 = 5;
+const GLYPH_HEIGHT: i32 = 7;
+/// Size of the button icons
+const ICON_SIZE: i32 = 10;
+
+/// 5x7 font for printable ASCII, starting from ' '.
+/// One byte per row, the leftmost pixel is 0x10.
+const FONT: [[u8; 7]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // '!'
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a], // '#'
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04], // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d], // '&'
+    [0x04, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00], // '*'
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08], // ','
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c], // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e], // '0'
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e], // '1'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f], // '2'
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e], // '3'
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02], // '4'
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e], // '5'
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e], // '6'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e], // '8'
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c], // '9'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00], // ':'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08], // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00], // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e], // '@'
+    [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'A'
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e], // 'B'
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e], // 'C'
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c], // 'D'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f], // 'E'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10], // 'F'
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f], // 'G'
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'H'
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f], // 'L'
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'O'
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10], // 'P'
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d], // 'Q'
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11], // 'R'
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e], // 'S'
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a], // 'W'
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11], // 'X'
+    [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04], // 'Y'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f], // 'Z'
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e], // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\\'
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e], // ']'
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f], // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f], // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e], // 'b'
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e], // 'c'
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f], // 'd'
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e], // 'e'
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08], // 'f'
+    [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e], // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c], // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'l'
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11], // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e], // 'o'
+    [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10], // 'p'
+    [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01], // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+    [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e], // 's'
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06], // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d], // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a], // 'w'
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11], // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'y'
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f], // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+/// Pixels of the title bar, BGRA as wl_shm's ARGB8888 is little endian
+fn draw_title_bar(width: i32, title: &str) -> Vec<u8> {
+    let height = Decorations::BAR_HEIGHT;
+    let mut pixels = BACKGROUND.repeat((width * height) as usize);
+    let mut put_pixel = |x: i32, y: i32| {
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            let offset = ((y * width + x) * 4) as usize;
+            pixels[offset..offset + 4].copy_from_slice(&FOREGROUND);
+        }
+    };
+
+    // the top WIDTH pixels of the bar are a resize border
+    let content_height = height - Decorations::WIDTH;
+
+    let title_end = width - Decorations::BAR_HEIGHT * 3 - GLYPH_WIDTH * FONT_SCALE;
+    let title_y = Decorations::WIDTH + (content_height - GLYPH_HEIGHT * FONT_SCALE) / 2;
+    let mut title_x = Decorations::WIDTH + 8;
+    for c in title.chars() {
+        if title_x + GLYPH_WIDTH * FONT_SCALE > title_end {
+            break;
+        }
+        // the font only has printable ASCII, anything else is left out
+        let glyph = match c {
+            ' '..='~' => FONT[c as usize - ' ' as usize],
+            _ => continue,
+        };
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0x10 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..FONT_SCALE {
+                    for dx in 0..FONT_SCALE {
+                        put_pixel(
+                            title_x + column * FONT_SCALE + dx,
+                            title_y + row as i32 * FONT_SCALE + dy,
+                        );
+                    }
+                }
+            }
+        }
+        title_x += (GLYPH_WIDTH + 1) * FONT_SCALE;
+    }
+
+    for (i, button) in [Button::Close, Button::Maximize, Button::Minimize]
+        .iter()
+        .enumerate()
+    {
+        let icon_x = width - Decorations::BAR_HEIGHT * (i as i32 + 1)
+            + (Decorations::BAR_HEIGHT - ICON_SIZE) / 2;
+        let icon_y = Decorations::WIDTH + (content_height - ICON_SIZE) / 2;
+        let last = ICON_SIZE - 1;
+        for y in 0..ICON_SIZE {
+            for x in 0..ICON_SIZE {
+                let inside = match *button {
+                    Button::Close => (x - y).abs() < 2 || (x + y - last).abs() < 2,
+                    Button::Maximize => x == 0 || x == last || y < 2 || y == last,
+                    Button::Minimize => y >= last - 1,
+                };
+                if inside {
+                    put_pixel(icon_x + x, icon_y + y);
+                }
+            }
+        }
+    }
+
+    pixels
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -75,33 +300,56 @@ unsafe fn create_decoration(
         surface,
         subsurface,
         viewport,
+        x,
+        y,
     }
 }
 
 impl Decorations {
-    pub const WIDTH: i32 = 2;
-    pub const BAR_HEIGHT: i32 = 15;
+    pub const WIDTH: i32 = 4;
+    pub const BAR_HEIGHT: i32 = 24;
+    /// How far along the edges the corners reach
+    const CORNER: i32 = 16;
 
-    pub unsafe fn new(display: &mut WaylandDisplay, width: i32, height: i32) -> Decorations {
-        let buffer = shm::create_shm_buffer(
+    /// Takes the size of the window content
+    pub unsafe fn new(
+        display: &mut WaylandDisplay,
+        width: i32,
+        height: i32,
+    ) -> std::io::Result<Decorations> {
+        let buffer = shm::create_shm_buffer(&mut display.client, display.shm, 1, 1, &BACKGROUND)?;
+        let title = display.title.clone();
+        let title_width = width + Self::WIDTH * 2;
+        let title_buffer = match shm::create_shm_buffer(
             &mut display.client,
             display.shm,
-            1,
-            1,
-            &[200, 200, 200, 255],
-        );
+            title_width,
+            Self::BAR_HEIGHT,
+            &draw_title_bar(title_width, &title),
+        ) {
+            Ok(title_buffer) => title_buffer,
+            Err(error) => {
+                wl_request!(display.client, buffer, WL_BUFFER_DESTROY);
+                (display.client.wl_proxy_destroy)(buffer as _);
+                return Err(error);
+            }
+        };
 
-        Decorations {
+        Ok(Decorations {
             buffer,
+            title_buffer,
+            title,
+            width,
+            height,
             top_decoration: create_decoration(
                 display,
                 display.compositor,
                 display.subcompositor,
                 display.surface,
-                buffer,
+                title_buffer,
                 -Self::WIDTH,
                 -Self::BAR_HEIGHT,
-                width + Self::WIDTH * Self::WIDTH,
+                title_width,
                 Self::BAR_HEIGHT,
             ),
             left_decoration: create_decoration(
@@ -111,9 +359,9 @@ impl Decorations {
                 display.surface,
                 buffer,
                 -Self::WIDTH,
-                -Self::BAR_HEIGHT,
+                0,
                 Self::WIDTH,
-                height + Self::BAR_HEIGHT,
+                height,
             ),
             right_decoration: create_decoration(
                 display,
@@ -122,9 +370,9 @@ impl Decorations {
                 display.surface,
                 buffer,
                 width,
-                -Self::BAR_HEIGHT,
+                0,
                 Self::WIDTH,
-                height + Self::BAR_HEIGHT,
+                height,
             ),
             bottom_decoration: create_decoration(
                 display,
@@ -134,13 +382,56 @@ impl Decorations {
                 buffer,
                 -Self::WIDTH,
                 height,
-                width + Self::WIDTH,
+                width + Self::WIDTH * 2,
                 Self::WIDTH,
             ),
+            hovered: None,
+            pointer_x: 0,
+            pointer_y: 0,
+            pressed: None,
+        })
+    }
+
+    pub unsafe fn destroy(self, client: &mut LibWaylandClient) {
+        for decoration in [
+            self.top_decoration,
+            self.bottom_decoration,
+            self.left_decoration,
+            self.right_decoration,
+        ] {
+            wl_request!(client, decoration.viewport, wp_viewport::destroy);
+            (client.wl_proxy_destroy)(decoration.viewport as _);
+            wl_request!(client, decoration.subsurface, WL_SUBSURFACE_DESTROY);
+            (client.wl_proxy_destroy)(decoration.subsurface as _);
+            wl_request!(client, decoration.surface, WL_SURFACE_DESTROY);
+            (client.wl_proxy_destroy)(decoration.surface as _);
+        }
+        for buffer in [self.buffer, self.title_buffer] {
+            wl_request!(client, buffer, WL_BUFFER_DESTROY);
+            (client.wl_proxy_destroy)(buffer as _);
         }
     }
 
-    pub unsafe fn resize(&self, client: &mut LibWaylandClient, width: i32, height: i32) {
+    /// Draws the title bar again, for a new width or title.
+    /// Without shared memory for the new one, the old title bar stays.
+    unsafe fn redraw_title_bar(&mut self, client: &mut LibWaylandClient, shm: *mut wl_shm) {
+        let width = self.width + Self::WIDTH * 2;
+        let buffer = match shm::create_shm_buffer(
+            client,
+            shm,
+            width,
+            Self::BAR_HEIGHT,
+            &draw_title_bar(width, &self.title),
+        ) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                eprintln!("Failed to draw the title bar: {}", error);
+                return;
+            }
+        };
+        let surface = self.top_decoration.surface;
+        wl_request!(client, surface, WL_SURFACE_ATTACH, buffer, 0, 0);
+        wl_request!(client, surface, WL_SURFACE_DAMAGE, 0, 0, i32::MAX, i32::MAX);
         wl_request!(
             client,
             self.top_decoration.viewport,
@@ -148,7 +439,42 @@ impl Decorations {
             width,
             Self::BAR_HEIGHT
         );
-        wl_request!(client, self.top_decoration.surface, WL_SURFACE_COMMIT);
+        wl_request!(client, surface, WL_SURFACE_COMMIT);
+
+        // the new buffer replaced the old one with the commit above
+        wl_request!(client, self.title_buffer, WL_BUFFER_DESTROY);
+        (client.wl_proxy_destroy)(self.title_buffer as _);
+        self.title_buffer = buffer;
+    }
+
+    pub unsafe fn set_title(
+        &mut self,
+        client: &mut LibWaylandClient,
+        shm: *mut wl_shm,
+        title: &str,
+    ) {
+        self.title = title.to_owned();
+        self.redraw_title_bar(client, shm);
+    }
+
+    /// Takes the size of the window content
+    pub unsafe fn resize(
+        &mut self,
+        client: &mut LibWaylandClient,
+        shm: *mut wl_shm,
+        width: i32,
+        height: i32,
+    ) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        let width_changed = width != self.width;
+        self.width = width;
+        self.height = height;
+
+        if width_changed {
+            self.redraw_title_bar(client, shm);
+        }
 
         wl_request!(
             client,
@@ -159,12 +485,13 @@ impl Decorations {
         );
         wl_request!(client, self.left_decoration.surface, WL_SURFACE_COMMIT);
 
+        self.right_decoration.x = width;
         wl_request!(
             client,
             self.right_decoration.subsurface,
             WL_SUBSURFACE_SET_POSITION,
-            width - Self::WIDTH * 2,
-            -Self::BAR_HEIGHT
+            width,
+            0
         );
         wl_request!(
             client,
@@ -175,20 +502,84 @@ impl Decorations {
         );
         wl_request!(client, self.right_decoration.surface, WL_SURFACE_COMMIT);
 
+        self.bottom_decoration.y = height;
         wl_request!(
             client,
             self.bottom_decoration.subsurface,
             WL_SUBSURFACE_SET_POSITION,
-            0,
-            height - Self::BAR_HEIGHT - Self::WIDTH
+            -Self::WIDTH,
+            height
         );
         wl_request!(
             client,
             self.bottom_decoration.viewport,
             wp_viewport::set_destination,
-            width - Self::WIDTH * 2,
+            width + Self::WIDTH * 2,
             Self::WIDTH
         );
         wl_request!(client, self.bottom_decoration.surface, WL_SURFACE_COMMIT);
     }
+
+    /// Which part of the decorations is at the given point of the window content coordinates
+    fn hit_test(&self, x: i32, y: i32) -> Area {
+        let (width, height) = (self.width, self.height);
+
+        let top = y < Self::WIDTH - Self::BAR_HEIGHT;
+        let bottom = y >= height;
+        // corners are easier to grab when they reach a bit along the edges
+        let left = x < 0 || ((top || bottom) && x < Self::CORNER - Self::WIDTH);
+        let right = x >= width || ((top || bottom) && x >= width + Self::WIDTH - Self::CORNER);
+        let top = top || ((left || right) && y < Self::CORNER - Self::BAR_HEIGHT);
+        let bottom = bottom || ((left || right) && y >= height + Self::WIDTH - Self::CORNER);
+
+        match (top, bottom, left, right) {
+            (true, _, true, _) => return Area::Resize(ResizeEdge::TopLeft),
+            (true, _, _, true) => return Area::Resize(ResizeEdge::TopRight),
+            (_, true, true, _) => return Area::Resize(ResizeEdge::BottomLeft),
+            (_, true, _, true) => return Area::Resize(ResizeEdge::BottomRight),
+            (true, _, _, _) => return Area::Resize(ResizeEdge::Top),
+            (_, true, _, _) => return Area::Resize(ResizeEdge::Bottom),
+            (_, _, true, _) => return Area::Resize(ResizeEdge::Left),
+            (_, _, _, true) => return Area::Resize(ResizeEdge::Right),
+            _ => {}
+        }
+
+        // the rest is the title bar, buttons are squares at its right end
+        let from_right = width + Self::WIDTH - x;
+        match from_right / Self::BAR_HEIGHT {
+            0 => Area::Button(Button::Close),
+            1 => Area::Button(Button::Maximize),
+            2 => Area::Button(Button::Minimize),
+            _ => Area::Move,
+        }
+    }
+
+    /// Updates the area under the pointer, `x` and `y` are local to `surface`.
+    /// Returns true if the cursor should change.
+    pub fn pointer_motion(&mut self, surface: *mut wl_surface, x: f64, y: f64) -> bool {
+        let position = [
+            &self.top_decoration,
+            &self.bottom_decoration,
+            &self.left_decoration,
+            &self.right_decoration,
+        ]
+        .iter()
+        .find(|decoration| decoration.surface == surface)
+        .map(|decoration| (decoration.x, decoration.y));
+        let Some((decoration_x, decoration_y)) = position else {
+            return false;
+        };
+        self.pointer_x = decoration_x + x as i32;
+        self.pointer_y = decoration_y + y as i32;
+
+        let area = Some(self.hit_test(self.pointer_x, self.pointer_y));
+        let cursor_changed = area.map(Area::cursor_icon) != self.hovered.map(Area::cursor_icon);
+        self.hovered = area;
+        cursor_changed
+    }
+
+    pub fn pointer_leave(&mut self) {
+        self.hovered = None;
+        self.pressed = None;
+    }
 }
@@ -1,5 +1,10 @@
-#![allow(unused_variables, dead_code, non_upper_case_globals)]
+#![allow(unused_variables, dead_code, non_upper_case_globals, unused_unsafe)]
 
+pub mod cursor_shape;
+pub mod fractional_scale;
+pub mod pointer_constraints;
+pub mod relative_pointer;
+pub mod text_input;
 pub mod viewporter;
 pub mod xdg_decoration;
 pub mod xdg_shell;
@@ -40,7 +45,7 @@ macro_rules! wayland_interface {
                 mod $method_name {
                     use super::*;
 
-                    pub static mut METHOD_ARGUMENTS_TYPES: [*const wl_interface; $crate::count!($($method_argument_name)*)] = [$(unsafe { &$method_argument_name as *const _},)*];
+                    pub static mut METHOD_ARGUMENTS_TYPES: [*const wl_interface; $crate::count!($($method_argument_name)*)] = [$(unsafe { std::ptr::addr_of!($method_argument_name) },)*];
 
                 }
             )*
@@ -48,7 +53,7 @@ macro_rules! wayland_interface {
             static mut requests: [wl_message; $crate::count!($($method_name)*)] = [$(wl_message {
                 name: concat!(stringify!($method_name), '\0').as_ptr() as _,
                 signature: concat!($method_sign, '\0').as_ptr() as _,
-                types: unsafe { $method_name::METHOD_ARGUMENTS_TYPES.as_ptr() as _ }
+                types: unsafe { std::ptr::addr_of!($method_name::METHOD_ARGUMENTS_TYPES) as _ }
             }), *];
 
             static mut events: [wl_message; $crate::count!($($event_name)*)] = [$(wl_message {
@@ -61,9 +66,9 @@ macro_rules! wayland_interface {
                 name: concat!(stringify!($struct_name), '\0').as_ptr() as *const _,
                 version: $version,
                 method_count: $crate::count!($($method_name)*) as i32,
-                methods: unsafe { requests.as_ptr() },
+                methods: unsafe { std::ptr::addr_of!(requests) as _ },
                 event_count: $crate::count!($($event_name)*) as i32,
-                events: unsafe { events.as_ptr() },
+                events: unsafe { std::ptr::addr_of!(events) as _ },
             };
         }
 
@@ -98,6 +103,24 @@ pub mod wayland_protocol {
         ]
     );
 
+    wayland_interface!(
+        wl_pointer_interface,
+        wl_pointer,
+        7,
+        [(set_cursor, "u?oii", ()), (release, "3", ())],
+        [
+            ("enter", "uoff"),
+            ("leave", "uo"),
+            ("motion", "uff"),
+            ("button", "uuuu"),
+            ("axis", "uuf"),
+            ("frame", "5"),
+            ("axis_source", "5u"),
+            ("axis_stop", "5uu"),
+            ("axis_discrete", "5ui")
+        ]
+    );
+
     wayland_interface!(
         wl_seat_interface,
         wl_seat,
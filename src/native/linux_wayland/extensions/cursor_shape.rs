@@ -0,0 +1,41 @@
+// cursor-shape-v1.xml
+
+use super::{
+    super::libwayland_client::{wl_interface, wl_message},
+    wayland_protocol::wl_pointer_interface,
+};
+use crate::wayland_interface;
+
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_DEFAULT: u32 = 1;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_HELP: u32 = 3;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_POINTER: u32 = 4;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_WAIT: u32 = 6;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_CROSSHAIR: u32 = 8;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_TEXT: u32 = 9;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_MOVE: u32 = 13;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NOT_ALLOWED: u32 = 15;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_EW_RESIZE: u32 = 26;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NS_RESIZE: u32 = 27;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NESW_RESIZE: u32 = 28;
+pub const WP_CURSOR_SHAPE_DEVICE_V1_SHAPE_NWSE_RESIZE: u32 = 29;
+
+#[rustfmt::skip]
+wayland_interface!(
+    wp_cursor_shape_manager_v1_interface,
+    wp_cursor_shape_manager_v1,
+    1,
+    [
+        (destroy, "", ()),
+        (get_pointer, "no", (wp_cursor_shape_device_v1_interface, wl_pointer_interface)),
+        (get_tablet_tool_v2, "no", ())
+    ],
+    []
+);
+
+wayland_interface!(
+    wp_cursor_shape_device_v1_interface,
+    wp_cursor_shape_device_v1,
+    1,
+    [(destroy, "", ()), (set_shape, "uu", ())],
+    []
+);
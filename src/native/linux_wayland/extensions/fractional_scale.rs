@@ -0,0 +1,40 @@
+// fractional-scale-v1.xml
+
+use super::super::libwayland_client::{wl_interface, wl_message};
+use crate::wayland_interface;
+
+wayland_interface!(
+    wp_fractional_scale_manager_v1_interface,
+    wp_fractional_scale_manager_v1,
+    1,
+    [
+        (destroy, "", ()),
+        (
+            get_fractional_scale,
+            "no",
+            (wp_fractional_scale_v1_interface)
+        )
+    ],
+    []
+);
+
+wayland_interface!(
+    wp_fractional_scale_v1_interface,
+    wp_fractional_scale_v1,
+    1,
+    [(destroy, "", ())],
+    [("preferred_scale", "u")]
+);
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub(crate) struct wp_fractional_scale_v1_listener {
+    /// The scale is the numerator of a fraction with a denominator of 120
+    pub preferred_scale: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut wp_fractional_scale_v1,
+            _: u32,
+        ) -> (),
+    >,
+}
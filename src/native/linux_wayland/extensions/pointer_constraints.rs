@@ -0,0 +1,40 @@
+// pointer-constraints-unstable-v1.xml
+
+use super::super::libwayland_client::{wl_interface, wl_message};
+use crate::wayland_interface;
+
+pub const ZWP_POINTER_CONSTRAINTS_V1_LIFETIME_ONESHOT: u32 = 1;
+pub const ZWP_POINTER_CONSTRAINTS_V1_LIFETIME_PERSISTENT: u32 = 2;
+
+#[rustfmt::skip]
+wayland_interface!(
+    zwp_pointer_constraints_v1_interface,
+    zwp_pointer_constraints_v1,
+    1,
+    [
+        (destroy, "", ()),
+        (lock_pointer, "noo?ou", (zwp_locked_pointer_v1_interface)),
+        (confine_pointer, "noo?ou", (zwp_confined_pointer_v1_interface))
+    ],
+    []
+);
+
+wayland_interface!(
+    zwp_locked_pointer_v1_interface,
+    zwp_locked_pointer_v1,
+    1,
+    [
+        (destroy, "", ()),
+        (set_cursor_position_hint, "ff", ()),
+        (set_region, "?o", ())
+    ],
+    [("locked", ""), ("unlocked", "")]
+);
+
+wayland_interface!(
+    zwp_confined_pointer_v1_interface,
+    zwp_confined_pointer_v1,
+    1,
+    [(destroy, "", ()), (set_region, "?o", ())],
+    [("confined", ""), ("unconfined", "")]
+);
@@ -0,0 +1,45 @@
+// relative-pointer-unstable-v1.xml
+
+use super::{
+    super::libwayland_client::{wl_fixed_t, wl_interface, wl_message},
+    wayland_protocol::wl_pointer_interface,
+};
+use crate::wayland_interface;
+
+#[rustfmt::skip]
+wayland_interface!(
+    zwp_relative_pointer_manager_v1_interface,
+    zwp_relative_pointer_manager_v1,
+    1,
+    [
+        (destroy, "", ()),
+        (get_relative_pointer, "no", (zwp_relative_pointer_v1_interface, wl_pointer_interface))
+    ],
+    []
+);
+
+wayland_interface!(
+    zwp_relative_pointer_v1_interface,
+    zwp_relative_pointer_v1,
+    1,
+    [(destroy, "", ())],
+    [("relative_motion", "uuffff")]
+);
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub(crate) struct zwp_relative_pointer_v1_listener {
+    /// utime_hi, utime_lo, dx, dy, dx_unaccel, dy_unaccel
+    pub relative_motion: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zwp_relative_pointer_v1,
+            _: u32,
+            _: u32,
+            _: wl_fixed_t,
+            _: wl_fixed_t,
+            _: wl_fixed_t,
+            _: wl_fixed_t,
+        ) -> (),
+    >,
+}
@@ -0,0 +1,92 @@
+// text-input-unstable-v3.xml
+
+use super::{
+    super::libwayland_client::{wl_interface, wl_message, wl_surface},
+    wayland_protocol::wl_seat_interface,
+};
+use crate::wayland_interface;
+
+#[rustfmt::skip]
+wayland_interface!(
+    zwp_text_input_manager_v3_interface,
+    zwp_text_input_manager_v3,
+    1,
+    [
+        (destroy, "", ()),
+        (get_text_input, "no", (zwp_text_input_v3_interface, wl_seat_interface))
+    ],
+    []
+);
+
+#[rustfmt::skip]
+wayland_interface!(
+    zwp_text_input_v3_interface,
+    zwp_text_input_v3,
+    1,
+    [
+        (destroy, "", ()),
+        (enable, "", ()),
+        (disable, "", ()),
+        (set_surrounding_text, "sii", ()),
+        (set_text_change_cause, "u", ()),
+        (set_content_type, "uu", ()),
+        (set_cursor_rectangle, "iiii", ()),
+        (commit, "", ())
+    ],
+    [
+        ("enter", "o"),
+        ("leave", "o"),
+        ("preedit_string", "?sii"),
+        ("commit_string", "?s"),
+        ("delete_surrounding_text", "uu"),
+        ("done", "u")
+    ]
+);
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub(crate) struct zwp_text_input_v3_listener {
+    pub enter: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zwp_text_input_v3,
+            _: *mut wl_surface,
+        ) -> (),
+    >,
+    pub leave: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zwp_text_input_v3,
+            _: *mut wl_surface,
+        ) -> (),
+    >,
+    /// text, cursor_begin, cursor_end
+    pub preedit_string: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zwp_text_input_v3,
+            _: *const libc::c_char,
+            _: i32,
+            _: i32,
+        ) -> (),
+    >,
+    pub commit_string: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zwp_text_input_v3,
+            _: *const libc::c_char,
+        ) -> (),
+    >,
+    /// before_length, after_length
+    pub delete_surrounding_text: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zwp_text_input_v3,
+            _: u32,
+            _: u32,
+        ) -> (),
+    >,
+    pub done: Option<
+        unsafe extern "C" fn(_: *mut std::ffi::c_void, _: *mut zwp_text_input_v3, _: u32) -> (),
+    >,
+}
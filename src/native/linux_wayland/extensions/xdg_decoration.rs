@@ -33,3 +33,15 @@ wayland_interface!(
         (unset_mode, "", ())],
     [("configure", "u")]
 );
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub(crate) struct zxdg_toplevel_decoration_v1_listener {
+    pub configure: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut zxdg_toplevel_decoration_v1,
+            _: u32,
+        ) -> (),
+    >,
+}
@@ -6,10 +6,35 @@ use super::{
 };
 use crate::wayland_interface;
 
+pub const XDG_TOPLEVEL_RESIZE_EDGE_NONE: u32 = 0;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_TOP: u32 = 1;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM: u32 = 2;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_LEFT: u32 = 4;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_TOP_LEFT: u32 = 5;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_LEFT: u32 = 6;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_RIGHT: u32 = 8;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_TOP_RIGHT: u32 = 9;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_RIGHT: u32 = 10;
+
+pub const XDG_TOPLEVEL_STATE_MAXIMIZED: u32 = 1;
+pub const XDG_TOPLEVEL_STATE_FULLSCREEN: u32 = 2;
+pub const XDG_TOPLEVEL_STATE_RESIZING: u32 = 3;
+pub const XDG_TOPLEVEL_STATE_ACTIVATED: u32 = 4;
+pub const XDG_TOPLEVEL_STATE_TILED_LEFT: u32 = 5;
+pub const XDG_TOPLEVEL_STATE_TILED_RIGHT: u32 = 6;
+pub const XDG_TOPLEVEL_STATE_TILED_TOP: u32 = 7;
+pub const XDG_TOPLEVEL_STATE_TILED_BOTTOM: u32 = 8;
+pub const XDG_TOPLEVEL_STATE_SUSPENDED: u32 = 9;
+
+pub const XDG_TOPLEVEL_WM_CAPABILITIES_WINDOW_MENU: u32 = 1;
+pub const XDG_TOPLEVEL_WM_CAPABILITIES_MAXIMIZE: u32 = 2;
+pub const XDG_TOPLEVEL_WM_CAPABILITIES_FULLSCREEN: u32 = 3;
+pub const XDG_TOPLEVEL_WM_CAPABILITIES_MINIMIZE: u32 = 4;
+
 wayland_interface!(
     xdg_wm_base_interface,
     xdg_wm_base,
-    3,
+    6,
     [
         (destroy, "", ()),
         (create_positioner, "n", (xdg_positioner_interface)),
@@ -22,11 +47,19 @@ wayland_interface!(
 wayland_interface!(
     xdg_surface_interface,
     xdg_surface,
-    3,
+    6,
     [
         (destroy, "", ()),
         (get_toplevel, "n", (xdg_toplevel_interface)),
-        (get_popup, "n?oo", ()),
+        (
+            get_popup,
+            "n?oo",
+            (
+                xdg_popup_interface,
+                xdg_surface_interface,
+                xdg_positioner_interface
+            )
+        ),
         (set_window_geometry, "iiii", ()),
         (ack_configure, "u", ())
     ],
@@ -36,30 +69,35 @@ wayland_interface!(
 wayland_interface!(
     xdg_toplevel_interface,
     xdg_toplevel,
-    3,
+    6,
     [
         (destroy, "", ()),
         (set_parent, "?o", (xdg_toplevel_interface)),
         (set_title, "s", ()),
         (set_app_id, "s", ()),
-        (show_window_menu, "ouii", (xdg_popup_interface)),
+        (show_window_menu, "ouii", (wl_seat_interface)),
         (r#move, "ou", (wl_seat_interface)),
         (resize, "ouu", (wl_seat_interface)),
         (set_max_size, "ii", ()),
         (set_min_size, "ii", ()),
         (set_maximized, "", ()),
         (unset_maximized, "", ()),
-        (set_fullscreen, "", (wl_output_interface)),
+        (set_fullscreen, "?o", (wl_output_interface)),
         (unset_fullscreen, "", ()),
         (set_minimized, "", ())
     ],
-    [("configure", "iia"), ("close", "")]
+    [
+        ("configure", "iia"),
+        ("close", ""),
+        ("configure_bounds", "4ii"),
+        ("wm_capabilities", "5a")
+    ]
 );
 
 wayland_interface!(
     xdg_positioner_interface,
     xdg_positioner,
-    3,
+    6,
     [
         (destroy, "", ()),
         (set_size, "ii", ()),
@@ -78,7 +116,7 @@ wayland_interface!(
 wayland_interface!(
     xdg_popup_interface,
     xdg_popup,
-    3,
+    6,
     [
         (destroy, "", ()),
         (grab, "ou", (wl_seat_interface)),
@@ -114,4 +152,14 @@ pub(crate) struct xdg_toplevel_listener {
         ) -> (),
     >,
     pub close: Option<unsafe extern "C" fn(_: *mut std::ffi::c_void, _: *mut xdg_toplevel) -> ()>,
+    pub configure_bounds: Option<
+        unsafe extern "C" fn(_: *mut std::ffi::c_void, _: *mut xdg_toplevel, _: i32, _: i32) -> (),
+    >,
+    pub wm_capabilities: Option<
+        unsafe extern "C" fn(
+            _: *mut std::ffi::c_void,
+            _: *mut xdg_toplevel,
+            _: *mut wl_array,
+        ) -> (),
+    >,
 }
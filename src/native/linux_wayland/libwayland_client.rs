@@ -191,6 +191,10 @@ pub const wl_shm_format_WL_SHM_FORMAT_ARGB8888: wl_shm_format = 0;
 
 pub type wl_fixed_t = i32;
 
+pub fn wl_fixed_to_double(f: wl_fixed_t) -> f64 {
+    f as f64 / 256.0
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct wl_object {
@@ -412,8 +416,391 @@ pub struct wl_seat_listener {
     >,
 }
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_data_offer_listener {
+    pub offer: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_offer: *mut wl_data_offer,
+            mime_type: *const ::std::os::raw::c_char,
+        ),
+    >,
+    pub source_actions: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_offer: *mut wl_data_offer,
+            source_actions: u32,
+        ),
+    >,
+    pub action: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_offer: *mut wl_data_offer,
+            dnd_action: u32,
+        ),
+    >,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_data_source_listener {
+    pub target: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_source: *mut wl_data_source,
+            mime_type: *const ::std::os::raw::c_char,
+        ),
+    >,
+    pub send: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_source: *mut wl_data_source,
+            mime_type: *const ::std::os::raw::c_char,
+            fd: i32,
+        ),
+    >,
+    pub cancelled: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_source: *mut wl_data_source,
+        ),
+    >,
+    pub dnd_drop_performed: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_source: *mut wl_data_source,
+        ),
+    >,
+    pub dnd_finished: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_source: *mut wl_data_source,
+        ),
+    >,
+    pub action: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_source: *mut wl_data_source,
+            dnd_action: u32,
+        ),
+    >,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_data_device_listener {
+    pub data_offer: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_device: *mut wl_data_device,
+            id: *mut wl_data_offer,
+        ),
+    >,
+    pub enter: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_device: *mut wl_data_device,
+            serial: u32,
+            surface: *mut wl_surface,
+            x: wl_fixed_t,
+            y: wl_fixed_t,
+            id: *mut wl_data_offer,
+        ),
+    >,
+    pub leave: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_device: *mut wl_data_device,
+        ),
+    >,
+    pub motion: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_device: *mut wl_data_device,
+            time: u32,
+            x: wl_fixed_t,
+            y: wl_fixed_t,
+        ),
+    >,
+    pub drop: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_device: *mut wl_data_device,
+        ),
+    >,
+    pub selection: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_data_device: *mut wl_data_device,
+            id: *mut wl_data_offer,
+        ),
+    >,
+}
+pub const wl_pointer_button_state_WL_POINTER_BUTTON_STATE_RELEASED: wl_pointer_button_state = 0;
+pub const wl_pointer_button_state_WL_POINTER_BUTTON_STATE_PRESSED: wl_pointer_button_state = 1;
+pub type wl_pointer_button_state = ::std::os::raw::c_uint;
+pub const wl_pointer_axis_WL_POINTER_AXIS_VERTICAL_SCROLL: wl_pointer_axis = 0;
+pub const wl_pointer_axis_WL_POINTER_AXIS_HORIZONTAL_SCROLL: wl_pointer_axis = 1;
+pub type wl_pointer_axis = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_pointer_listener {
+    pub enter: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            serial: u32,
+            surface: *mut wl_surface,
+            surface_x: wl_fixed_t,
+            surface_y: wl_fixed_t,
+        ),
+    >,
+    pub leave: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            serial: u32,
+            surface: *mut wl_surface,
+        ),
+    >,
+    pub motion: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            time: u32,
+            surface_x: wl_fixed_t,
+            surface_y: wl_fixed_t,
+        ),
+    >,
+    pub button: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            serial: u32,
+            time: u32,
+            button: u32,
+            state: u32,
+        ),
+    >,
+    pub axis: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            time: u32,
+            axis: u32,
+            value: wl_fixed_t,
+        ),
+    >,
+    pub frame: ::std::option::Option<
+        unsafe extern "C" fn(data: *mut ::std::os::raw::c_void, wl_pointer: *mut wl_pointer),
+    >,
+    pub axis_source: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            axis_source: u32,
+        ),
+    >,
+    pub axis_stop: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            time: u32,
+            axis: u32,
+        ),
+    >,
+    pub axis_discrete: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_pointer: *mut wl_pointer,
+            axis: u32,
+            discrete: i32,
+        ),
+    >,
+}
+pub const wl_keyboard_keymap_format_WL_KEYBOARD_KEYMAP_FORMAT_NO_KEYMAP: wl_keyboard_keymap_format =
+    0;
+pub const wl_keyboard_keymap_format_WL_KEYBOARD_KEYMAP_FORMAT_XKB_V1: wl_keyboard_keymap_format = 1;
+pub type wl_keyboard_keymap_format = ::std::os::raw::c_uint;
+pub const wl_keyboard_key_state_WL_KEYBOARD_KEY_STATE_RELEASED: wl_keyboard_key_state = 0;
+pub const wl_keyboard_key_state_WL_KEYBOARD_KEY_STATE_PRESSED: wl_keyboard_key_state = 1;
+pub type wl_keyboard_key_state = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_keyboard_listener {
+    pub keymap: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_keyboard: *mut wl_keyboard,
+            format: u32,
+            fd: i32,
+            size: u32,
+        ),
+    >,
+    pub enter: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_keyboard: *mut wl_keyboard,
+            serial: u32,
+            surface: *mut wl_surface,
+            keys: *mut wl_array,
+        ),
+    >,
+    pub leave: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_keyboard: *mut wl_keyboard,
+            serial: u32,
+            surface: *mut wl_surface,
+        ),
+    >,
+    pub key: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_keyboard: *mut wl_keyboard,
+            serial: u32,
+            time: u32,
+            key: u32,
+            state: u32,
+        ),
+    >,
+    pub modifiers: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_keyboard: *mut wl_keyboard,
+            serial: u32,
+            mods_depressed: u32,
+            mods_latched: u32,
+            mods_locked: u32,
+            group: u32,
+        ),
+    >,
+    pub repeat_info: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_keyboard: *mut wl_keyboard,
+            rate: i32,
+            delay: i32,
+        ),
+    >,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_touch_listener {
+    pub down: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_touch: *mut wl_touch,
+            serial: u32,
+            time: u32,
+            surface: *mut wl_surface,
+            id: i32,
+            x: wl_fixed_t,
+            y: wl_fixed_t,
+        ),
+    >,
+    pub up: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_touch: *mut wl_touch,
+            serial: u32,
+            time: u32,
+            id: i32,
+        ),
+    >,
+    pub motion: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_touch: *mut wl_touch,
+            time: u32,
+            id: i32,
+            x: wl_fixed_t,
+            y: wl_fixed_t,
+        ),
+    >,
+    pub frame: ::std::option::Option<
+        unsafe extern "C" fn(data: *mut ::std::os::raw::c_void, wl_touch: *mut wl_touch),
+    >,
+    pub cancel: ::std::option::Option<
+        unsafe extern "C" fn(data: *mut ::std::os::raw::c_void, wl_touch: *mut wl_touch),
+    >,
+    pub shape: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_touch: *mut wl_touch,
+            id: i32,
+            major: wl_fixed_t,
+            minor: wl_fixed_t,
+        ),
+    >,
+    pub orientation: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_touch: *mut wl_touch,
+            id: i32,
+            orientation: wl_fixed_t,
+        ),
+    >,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_surface_listener {
+    pub enter: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_surface: *mut wl_surface,
+            output: *mut wl_output,
+        ),
+    >,
+    pub leave: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_surface: *mut wl_surface,
+            output: *mut wl_output,
+        ),
+    >,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_output_listener {
+    pub geometry: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_output: *mut wl_output,
+            x: i32,
+            y: i32,
+            physical_width: i32,
+            physical_height: i32,
+            subpixel: i32,
+            make: *const ::std::os::raw::c_char,
+            model: *const ::std::os::raw::c_char,
+            transform: i32,
+        ),
+    >,
+    pub mode: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_output: *mut wl_output,
+            flags: u32,
+            width: i32,
+            height: i32,
+            refresh: i32,
+        ),
+    >,
+    pub done: ::std::option::Option<
+        unsafe extern "C" fn(data: *mut ::std::os::raw::c_void, wl_output: *mut wl_output),
+    >,
+    pub scale: ::std::option::Option<
+        unsafe extern "C" fn(
+            data: *mut ::std::os::raw::c_void,
+            wl_output: *mut wl_output,
+            factor: i32,
+        ),
+    >,
+}
+
 pub type wl_display_connect =
     unsafe extern "C" fn(name: *const ::std::os::raw::c_char) -> *mut wl_display;
+pub type wl_display_disconnect = unsafe extern "C" fn(display: *mut wl_display);
 pub type wl_proxy_destroy = unsafe extern "C" fn(proxy: *mut wl_proxy);
 pub type wl_proxy_marshal = unsafe extern "C" fn(p: *mut wl_proxy, opcode: u32, ...);
 pub type wl_proxy_marshal_constructor = unsafe extern "C" fn(
@@ -439,11 +826,27 @@ pub type wl_display_roundtrip =
     unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
 pub type wl_display_dispatch_pending =
     unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
+pub type wl_display_flush = unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
+pub type wl_display_get_fd =
+    unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
+pub type wl_display_prepare_read =
+    unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
+pub type wl_display_read_events =
+    unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
+pub type wl_display_cancel_read = unsafe extern "C" fn(display: *mut wl_display);
+pub type wl_display_get_error =
+    unsafe extern "C" fn(display: *mut wl_display) -> ::std::os::raw::c_int;
+pub type wl_display_get_protocol_error = unsafe extern "C" fn(
+    display: *mut wl_display,
+    interface: *mut *const wl_interface,
+    id: *mut u32,
+) -> u32;
 
 #[derive(Clone)]
 pub struct LibWaylandClient {
     _module: std::rc::Rc<crate::native::module::Module>,
     pub wl_display_connect: wl_display_connect,
+    pub wl_display_disconnect: wl_display_disconnect,
     pub wl_proxy_destroy: wl_proxy_destroy,
     pub wl_proxy_marshal: wl_proxy_marshal,
     pub wl_proxy_marshal_constructor: wl_proxy_marshal_constructor,
@@ -451,14 +854,29 @@ pub struct LibWaylandClient {
     pub wl_display_dispatch_pending: wl_display_dispatch_pending,
     pub wl_proxy_add_listener: wl_proxy_add_listener,
     pub wl_display_roundtrip: wl_display_roundtrip,
+    pub wl_display_flush: wl_display_flush,
+    pub wl_display_get_fd: wl_display_get_fd,
+    pub wl_display_prepare_read: wl_display_prepare_read,
+    pub wl_display_read_events: wl_display_read_events,
+    pub wl_display_cancel_read: wl_display_cancel_read,
+    pub wl_display_get_error: wl_display_get_error,
+    pub wl_display_get_protocol_error: wl_display_get_protocol_error,
     pub wl_registry_interface: *mut wl_interface,
     pub wl_compositor_interface: *mut wl_interface,
     pub wl_subcompositor_interface: *mut wl_interface,
     pub wl_surface_interface: *mut wl_interface,
+    pub wl_callback_interface: *mut wl_interface,
     pub wl_subsurface_interface: *mut wl_interface,
     pub wl_buffer_interface: *mut wl_interface,
     pub wl_seat_interface: *mut wl_interface,
+    pub wl_pointer_interface: *mut wl_interface,
+    pub wl_keyboard_interface: *mut wl_interface,
+    pub wl_touch_interface: *mut wl_interface,
     pub wl_shm_interface: *mut wl_interface,
+    pub wl_data_device_manager_interface: *mut wl_interface,
+    pub wl_data_device_interface: *mut wl_interface,
+    pub wl_data_source_interface: *mut wl_interface,
+    pub wl_data_offer_interface: *mut wl_interface,
     pub wl_shm_pool_interface: *mut wl_interface,
 }
 
@@ -468,6 +886,7 @@ impl LibWaylandClient {
             .or_else(|_| crate::native::module::Module::load("libwayland-client.so.0"))
             .map(|module| LibWaylandClient {
                 wl_display_connect: module.get_symbol("wl_display_connect").unwrap(),
+                wl_display_disconnect: module.get_symbol("wl_display_disconnect").unwrap(),
                 wl_proxy_add_listener: module.get_symbol("wl_proxy_add_listener").unwrap(),
                 wl_display_dispatch_pending: module
                     .get_symbol("wl_display_dispatch_pending")
@@ -482,6 +901,15 @@ impl LibWaylandClient {
                     .get_symbol("wl_proxy_marshal_constructor_versioned")
                     .unwrap(),
                 wl_display_roundtrip: module.get_symbol("wl_display_roundtrip").unwrap(),
+                wl_display_flush: module.get_symbol("wl_display_flush").unwrap(),
+                wl_display_get_fd: module.get_symbol("wl_display_get_fd").unwrap(),
+                wl_display_prepare_read: module.get_symbol("wl_display_prepare_read").unwrap(),
+                wl_display_read_events: module.get_symbol("wl_display_read_events").unwrap(),
+                wl_display_cancel_read: module.get_symbol("wl_display_cancel_read").unwrap(),
+                wl_display_get_error: module.get_symbol("wl_display_get_error").unwrap(),
+                wl_display_get_protocol_error: module
+                    .get_symbol("wl_display_get_protocol_error")
+                    .unwrap(),
 
                 wl_registry_interface: module.get_symbol("wl_registry_interface").unwrap(),
                 wl_compositor_interface: module.get_symbol("wl_compositor_interface").unwrap(),
@@ -489,10 +917,20 @@ impl LibWaylandClient {
                     .get_symbol("wl_subcompositor_interface")
                     .unwrap(),
                 wl_surface_interface: module.get_symbol("wl_surface_interface").unwrap(),
+                wl_callback_interface: module.get_symbol("wl_callback_interface").unwrap(),
                 wl_subsurface_interface: module.get_symbol("wl_subsurface_interface").unwrap(),
                 wl_buffer_interface: module.get_symbol("wl_buffer_interface").unwrap(),
                 wl_seat_interface: module.get_symbol("wl_seat_interface").unwrap(),
+                wl_pointer_interface: module.get_symbol("wl_pointer_interface").unwrap(),
+                wl_keyboard_interface: module.get_symbol("wl_keyboard_interface").unwrap(),
+                wl_touch_interface: module.get_symbol("wl_touch_interface").unwrap(),
                 wl_shm_interface: module.get_symbol("wl_shm_interface").unwrap(),
+                wl_data_device_manager_interface: module
+                    .get_symbol("wl_data_device_manager_interface")
+                    .unwrap(),
+                wl_data_device_interface: module.get_symbol("wl_data_device_interface").unwrap(),
+                wl_data_source_interface: module.get_symbol("wl_data_source_interface").unwrap(),
+                wl_data_offer_interface: module.get_symbol("wl_data_offer_interface").unwrap(),
                 wl_shm_pool_interface: module.get_symbol("wl_shm_pool_interface").unwrap(),
 
                 _module: std::rc::Rc::new(module),
@@ -0,0 +1,70 @@
+#![allow(non_camel_case_types, dead_code)]
+
+use super::libwayland_client::{wl_buffer, wl_shm};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_cursor_theme {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_cursor_image {
+    pub width: u32,
+    pub height: u32,
+    pub hotspot_x: u32,
+    pub hotspot_y: u32,
+    pub delay: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wl_cursor {
+    pub image_count: ::std::os::raw::c_uint,
+    pub images: *mut *mut wl_cursor_image,
+    pub name: *mut ::std::os::raw::c_char,
+}
+
+pub type wl_cursor_theme_load = unsafe extern "C" fn(
+    name: *const ::std::os::raw::c_char,
+    size: ::std::os::raw::c_int,
+    shm: *mut wl_shm,
+) -> *mut wl_cursor_theme;
+
+pub type wl_cursor_theme_destroy = unsafe extern "C" fn(theme: *mut wl_cursor_theme);
+
+pub type wl_cursor_theme_get_cursor = unsafe extern "C" fn(
+    theme: *mut wl_cursor_theme,
+    name: *const ::std::os::raw::c_char,
+) -> *mut wl_cursor;
+
+pub type wl_cursor_image_get_buffer =
+    unsafe extern "C" fn(image: *mut wl_cursor_image) -> *mut wl_buffer;
+
+pub struct LibWaylandCursor {
+    _module: crate::native::module::Module,
+    pub wl_cursor_theme_load: wl_cursor_theme_load,
+    pub wl_cursor_theme_destroy: wl_cursor_theme_destroy,
+    pub wl_cursor_theme_get_cursor: wl_cursor_theme_get_cursor,
+    pub wl_cursor_image_get_buffer: wl_cursor_image_get_buffer,
+}
+
+impl LibWaylandCursor {
+    pub fn try_load() -> Option<LibWaylandCursor> {
+        crate::native::module::Module::load("libwayland-cursor.so")
+            .or_else(|_| crate::native::module::Module::load("libwayland-cursor.so.0"))
+            .map(|module| LibWaylandCursor {
+                wl_cursor_theme_load: module.get_symbol("wl_cursor_theme_load").unwrap(),
+                wl_cursor_theme_destroy: module.get_symbol("wl_cursor_theme_destroy").unwrap(),
+                wl_cursor_theme_get_cursor: module
+                    .get_symbol("wl_cursor_theme_get_cursor")
+                    .unwrap(),
+                wl_cursor_image_get_buffer: module
+                    .get_symbol("wl_cursor_image_get_buffer")
+                    .unwrap(),
+                _module: module,
+            })
+            .ok()
+    }
+}
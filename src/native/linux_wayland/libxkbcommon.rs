@@ -0,0 +1,117 @@
+#![allow(non_camel_case_types, dead_code)]
+
+pub const XKB_CONTEXT_NO_FLAGS: u32 = 0;
+pub const XKB_KEYMAP_FORMAT_TEXT_V1: u32 = 1;
+pub const XKB_KEYMAP_COMPILE_NO_FLAGS: u32 = 0;
+pub const XKB_STATE_MODS_EFFECTIVE: u32 = 1 << 3;
+
+pub const XKB_MOD_NAME_SHIFT: &[u8] = b"Shift\0";
+pub const XKB_MOD_NAME_CTRL: &[u8] = b"Control\0";
+pub const XKB_MOD_NAME_ALT: &[u8] = b"Mod1\0";
+pub const XKB_MOD_NAME_LOGO: &[u8] = b"Mod4\0";
+
+pub type xkb_keycode_t = u32;
+pub type xkb_keysym_t = u32;
+pub type xkb_layout_index_t = u32;
+pub type xkb_level_index_t = u32;
+pub type xkb_mod_mask_t = u32;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xkb_context {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xkb_keymap {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xkb_state {
+    _unused: [u8; 0],
+}
+
+pub type xkb_context_new = unsafe extern "C" fn(flags: u32) -> *mut xkb_context;
+pub type xkb_context_unref = unsafe extern "C" fn(context: *mut xkb_context);
+pub type xkb_keymap_new_from_string = unsafe extern "C" fn(
+    context: *mut xkb_context,
+    string: *const ::std::os::raw::c_char,
+    format: u32,
+    flags: u32,
+) -> *mut xkb_keymap;
+pub type xkb_keymap_unref = unsafe extern "C" fn(keymap: *mut xkb_keymap);
+pub type xkb_keymap_key_repeats =
+    unsafe extern "C" fn(keymap: *mut xkb_keymap, key: xkb_keycode_t) -> ::std::os::raw::c_int;
+pub type xkb_keymap_key_get_syms_by_level = unsafe extern "C" fn(
+    keymap: *mut xkb_keymap,
+    key: xkb_keycode_t,
+    layout: xkb_layout_index_t,
+    level: xkb_level_index_t,
+    syms_out: *mut *const xkb_keysym_t,
+) -> ::std::os::raw::c_int;
+pub type xkb_state_new = unsafe extern "C" fn(keymap: *mut xkb_keymap) -> *mut xkb_state;
+pub type xkb_state_unref = unsafe extern "C" fn(state: *mut xkb_state);
+pub type xkb_state_update_mask = unsafe extern "C" fn(
+    state: *mut xkb_state,
+    depressed_mods: xkb_mod_mask_t,
+    latched_mods: xkb_mod_mask_t,
+    locked_mods: xkb_mod_mask_t,
+    depressed_layout: xkb_layout_index_t,
+    latched_layout: xkb_layout_index_t,
+    locked_layout: xkb_layout_index_t,
+) -> u32;
+pub type xkb_state_key_get_layout =
+    unsafe extern "C" fn(state: *mut xkb_state, key: xkb_keycode_t) -> xkb_layout_index_t;
+pub type xkb_state_key_get_utf32 =
+    unsafe extern "C" fn(state: *mut xkb_state, key: xkb_keycode_t) -> u32;
+pub type xkb_state_mod_name_is_active = unsafe extern "C" fn(
+    state: *mut xkb_state,
+    name: *const ::std::os::raw::c_char,
+    type_: u32,
+) -> ::std::os::raw::c_int;
+
+pub struct LibXkbCommon {
+    _module: crate::native::module::Module,
+    pub xkb_context_new: xkb_context_new,
+    pub xkb_context_unref: xkb_context_unref,
+    pub xkb_keymap_new_from_string: xkb_keymap_new_from_string,
+    pub xkb_keymap_unref: xkb_keymap_unref,
+    pub xkb_keymap_key_repeats: xkb_keymap_key_repeats,
+    pub xkb_keymap_key_get_syms_by_level: xkb_keymap_key_get_syms_by_level,
+    pub xkb_state_new: xkb_state_new,
+    pub xkb_state_unref: xkb_state_unref,
+    pub xkb_state_update_mask: xkb_state_update_mask,
+    pub xkb_state_key_get_layout: xkb_state_key_get_layout,
+    pub xkb_state_key_get_utf32: xkb_state_key_get_utf32,
+    pub xkb_state_mod_name_is_active: xkb_state_mod_name_is_active,
+}
+
+impl LibXkbCommon {
+    pub fn try_load() -> Option<LibXkbCommon> {
+        crate::native::module::Module::load("libxkbcommon.so")
+            .or_else(|_| crate::native::module::Module::load("libxkbcommon.so.0"))
+            .map(|module| LibXkbCommon {
+                xkb_context_new: module.get_symbol("xkb_context_new").unwrap(),
+                xkb_context_unref: module.get_symbol("xkb_context_unref").unwrap(),
+                xkb_keymap_new_from_string: module
+                    .get_symbol("xkb_keymap_new_from_string")
+                    .unwrap(),
+                xkb_keymap_unref: module.get_symbol("xkb_keymap_unref").unwrap(),
+                xkb_keymap_key_repeats: module.get_symbol("xkb_keymap_key_repeats").unwrap(),
+                xkb_keymap_key_get_syms_by_level: module
+                    .get_symbol("xkb_keymap_key_get_syms_by_level")
+                    .unwrap(),
+                xkb_state_new: module.get_symbol("xkb_state_new").unwrap(),
+                xkb_state_unref: module.get_symbol("xkb_state_unref").unwrap(),
+                xkb_state_update_mask: module.get_symbol("xkb_state_update_mask").unwrap(),
+                xkb_state_key_get_layout: module.get_symbol("xkb_state_key_get_layout").unwrap(),
+                xkb_state_key_get_utf32: module.get_symbol("xkb_state_key_get_utf32").unwrap(),
+                xkb_state_mod_name_is_active: module
+                    .get_symbol("xkb_state_mod_name_is_active")
+                    .unwrap(),
+                _module: module,
+            })
+            .ok()
+    }
+}
@@ -0,0 +1,181 @@
+//! Cursor grab and raw mouse motion.
+//! A grabbed cursor is confined to the window with zwp_pointer_constraints_v1, or locked
+//! in place when it is also hidden - the usual first person camera setup, where only
+//! raw_mouse_motion matters. Raw motion is the unaccelerated delta from
+//! zwp_relative_pointer_v1, as X11 reports with XI_RawMotion.
+
+use super::{
+    extensions::{pointer_constraints::*, relative_pointer::*},
+    libwayland_client::*,
+    tl_display, WaylandDisplay, WaylandPayload,
+};
+use crate::{wl_request, wl_request_constructor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Constraint {
+    Lock,
+    Confine,
+}
+
+pub(crate) struct PointerGrab {
+    pub grab: bool,
+    relative_pointer: *mut zwp_relative_pointer_v1,
+    locked_pointer: *mut zwp_locked_pointer_v1,
+    confined_pointer: *mut zwp_confined_pointer_v1,
+}
+
+impl PointerGrab {
+    pub fn new() -> PointerGrab {
+        PointerGrab {
+            grab: false,
+            relative_pointer: std::ptr::null_mut(),
+            locked_pointer: std::ptr::null_mut(),
+            confined_pointer: std::ptr::null_mut(),
+        }
+    }
+
+    fn constraint(&self) -> Option<Constraint> {
+        if !self.locked_pointer.is_null() {
+            Some(Constraint::Lock)
+        } else if !self.confined_pointer.is_null() {
+            Some(Constraint::Confine)
+        } else {
+            None
+        }
+    }
+}
+
+static mut RELATIVE_POINTER_LISTENER: zwp_relative_pointer_v1_listener =
+    zwp_relative_pointer_v1_listener {
+        relative_motion: Some(relative_pointer_handle_relative_motion),
+    };
+
+/// Called when the seat gets a pointer, `data` is the WaylandPayload
+pub(super) unsafe fn init_pointer(display: &mut WaylandDisplay, data: *mut std::ffi::c_void) {
+    if !display.relative_pointer_manager.is_null() {
+        display.pointer_grab.relative_pointer = wl_request_constructor!(
+            display.client,
+            display.relative_pointer_manager,
+            zwp_relative_pointer_manager_v1::get_relative_pointer,
+            std::ptr::addr_of!(zwp_relative_pointer_v1_interface),
+            display.pointer
+        );
+        (display.client.wl_proxy_add_listener)(
+            display.pointer_grab.relative_pointer as _,
+            std::ptr::addr_of!(RELATIVE_POINTER_LISTENER) as _,
+            data,
+        );
+    }
+    update_constraint(display);
+}
+
+/// Called before the pointer is destroyed
+pub(super) unsafe fn release_pointer(display: &mut WaylandDisplay) {
+    set_constraint(display, None);
+    let relative_pointer = display.pointer_grab.relative_pointer;
+    if !relative_pointer.is_null() {
+        wl_request!(
+            display.client,
+            relative_pointer,
+            zwp_relative_pointer_v1::destroy
+        );
+        (display.client.wl_proxy_destroy)(relative_pointer as _);
+        display.pointer_grab.relative_pointer = std::ptr::null_mut();
+    }
+}
+
+/// Applies the grab and cursor visibility, should be called whenever any of them changes
+pub(super) unsafe fn update_constraint(display: &mut WaylandDisplay) {
+    let constraint = if !display.pointer_grab.grab
+        || display.pointer.is_null()
+        || display.pointer_constraints.is_null()
+    {
+        None
+    } else if display.cursor_visible {
+        Some(Constraint::Confine)
+    } else {
+        Some(Constraint::Lock)
+    };
+    set_constraint(display, constraint);
+}
+
+unsafe fn set_constraint(display: &mut WaylandDisplay, constraint: Option<Constraint>) {
+    if constraint == display.pointer_grab.constraint() {
+        return;
+    }
+
+    let grab = &mut display.pointer_grab;
+    if !grab.locked_pointer.is_null() {
+        wl_request!(
+            display.client,
+            grab.locked_pointer,
+            zwp_locked_pointer_v1::destroy
+        );
+        (display.client.wl_proxy_destroy)(grab.locked_pointer as _);
+        grab.locked_pointer = std::ptr::null_mut();
+    }
+    if !grab.confined_pointer.is_null() {
+        wl_request!(
+            display.client,
+            grab.confined_pointer,
+            zwp_confined_pointer_v1::destroy
+        );
+        (display.client.wl_proxy_destroy)(grab.confined_pointer as _);
+        grab.confined_pointer = std::ptr::null_mut();
+    }
+
+    // persistent constraints come back by themselves when the window gets the pointer again,
+    // just like an X11 pointer grab survives focus changes
+    match constraint {
+        Some(Constraint::Lock) => {
+            grab.locked_pointer = wl_request_constructor!(
+                display.client,
+                display.pointer_constraints,
+                zwp_pointer_constraints_v1::lock_pointer,
+                std::ptr::addr_of!(zwp_locked_pointer_v1_interface),
+                display.surface,
+                display.pointer,
+                std::ptr::null_mut::<wl_region>(),
+                ZWP_POINTER_CONSTRAINTS_V1_LIFETIME_PERSISTENT
+            );
+        }
+        Some(Constraint::Confine) => {
+            grab.confined_pointer = wl_request_constructor!(
+                display.client,
+                display.pointer_constraints,
+                zwp_pointer_constraints_v1::confine_pointer,
+                std::ptr::addr_of!(zwp_confined_pointer_v1_interface),
+                display.surface,
+                display.pointer,
+                std::ptr::null_mut::<wl_region>(),
+                ZWP_POINTER_CONSTRAINTS_V1_LIFETIME_PERSISTENT
+            );
+        }
+        None => {}
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn relative_pointer_handle_relative_motion(
+    data: *mut std::ffi::c_void,
+    _relative_pointer: *mut zwp_relative_pointer_v1,
+    _utime_hi: u32,
+    _utime_lo: u32,
+    _dx: wl_fixed_t,
+    _dy: wl_fixed_t,
+    dx_unaccel: wl_fixed_t,
+    dy_unaccel: wl_fixed_t,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+
+    if !tl_display::with(|display| display.pointer_focus == display.surface) {
+        return;
+    }
+    if let Some(event_handler) = payload.input_handler() {
+        event_handler.raw_mouse_motion(
+            wl_fixed_to_double(dx_unaccel) as f32,
+            wl_fixed_to_double(dy_unaccel) as f32,
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! HiDPI support.
+//! Each wl_output has an integer scale, and the surface should be rendered at the highest
+//! scale of the outputs it is on. wp_fractional_scale_v1, if available, overrides it
+//! with a fractional one; the buffer is then drawn at 1x buffer scale and shrunk back to the
+//! logical window size with wp_viewport.
+
+use super::{
+    extensions::{
+        fractional_scale::*,
+        viewporter::{wp_viewport, wp_viewport_interface, wp_viewporter},
+        wayland_protocol::wl_output_interface,
+    },
+    libwayland_client::*,
+    tl_display, WaylandDisplay,
+};
+use crate::{wl_request, wl_request_constructor};
+
+use std::collections::HashMap;
+
+/// Listener data of a wl_output.
+/// Outputs are bound straight from the registry, possibly before the display
+/// got into the thread local, so the scale lives in its own allocation.
+struct Output {
+    /// Registry global name, to find the output again once it is removed
+    name: u32,
+    version: u32,
+    scale: i32,
+}
+
+pub(crate) struct Scaling {
+    outputs: HashMap<*mut wl_output, Box<Output>>,
+    /// Outputs the window surface is currently on
+    surface_outputs: Vec<*mut wl_output>,
+    fractional_scale: *mut wp_fractional_scale_v1,
+    /// Preferred scale from wp_fractional_scale_v1, in 120ths
+    preferred_scale: Option<u32>,
+    pub viewport: *mut wp_viewport,
+}
+
+impl Scaling {
+    pub fn new() -> Scaling {
+        Scaling {
+            outputs: HashMap::new(),
+            surface_outputs: vec![],
+            fractional_scale: std::ptr::null_mut(),
+            preferred_scale: None,
+            viewport: std::ptr::null_mut(),
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        if let Some(preferred_scale) = self.preferred_scale {
+            return preferred_scale as f32 / 120.;
+        }
+        self.surface_outputs
+            .iter()
+            .filter_map(|output| self.outputs.get(output))
+            .map(|output| output.scale)
+            .max()
+            .unwrap_or(1) as f32
+    }
+}
+
+static mut OUTPUT_LISTENER: wl_output_listener = wl_output_listener {
+    geometry: Some(output_handle_geometry),
+    mode: Some(output_handle_mode),
+    done: Some(output_handle_done),
+    scale: Some(output_handle_scale),
+};
+
+pub(super) static mut SURFACE_LISTENER: wl_surface_listener = wl_surface_listener {
+    enter: Some(surface_handle_enter),
+    leave: Some(surface_handle_leave),
+};
+
+static mut FRACTIONAL_SCALE_LISTENER: wp_fractional_scale_v1_listener =
+    wp_fractional_scale_v1_listener {
+        preferred_scale: Some(fractional_scale_handle_preferred_scale),
+    };
+
+/// Called from the registry for each wl_output global
+pub(super) unsafe fn bind_output(
+    display: &mut WaylandDisplay,
+    registry: *mut wl_registry,
+    name: u32,
+    version: u32,
+) {
+    let version = 3.min(version);
+    let output: *mut wl_output = display.client.wl_registry_bind(
+        registry,
+        name,
+        std::ptr::addr_of!(wl_output_interface),
+        version,
+    ) as _;
+    let mut data = Box::new(Output {
+        name,
+        version,
+        scale: 1,
+    });
+    (display.client.wl_proxy_add_listener)(
+        output as _,
+        std::ptr::addr_of!(OUTPUT_LISTENER) as _,
+        &mut *data as *mut _ as _,
+    );
+    display.scaling.outputs.insert(output, data);
+}
+
+/// Called from the registry when a global goes away, like an unplugged monitor.
+/// Returns whether `name` was a wl_output.
+pub(super) unsafe fn remove_output(display: &mut WaylandDisplay, name: u32) -> bool {
+    let output = match display
+        .scaling
+        .outputs
+        .iter()
+        .find(|(_, output)| output.name == name)
+    {
+        Some((output, _)) => *output,
+        None => return false,
+    };
+    let data = display.scaling.outputs.remove(&output).unwrap();
+    display
+        .scaling
+        .surface_outputs
+        .retain(|surface_output| *surface_output != output);
+    if data.version >= WL_OUTPUT_RELEASE_SINCE_VERSION {
+        wl_request!(display.client, output, WL_OUTPUT_RELEASE);
+    }
+    (display.client.wl_proxy_destroy)(output as _);
+    true
+}
+
+/// Asks for the fractional scale of the window surface, if both
+/// wp_fractional_scale_manager_v1 and wp_viewporter are available
+pub(super) unsafe fn init_fractional_scale(display: &mut WaylandDisplay) {
+    let manager = display.fractional_scale_manager;
+    if manager.is_null() || display.viewporter.is_null() {
+        return;
+    }
+
+    display.scaling.viewport = wl_request_constructor!(
+        display.client,
+        display.viewporter,
+        wp_viewporter::get_viewport,
+        std::ptr::addr_of!(wp_viewport_interface),
+        display.surface
+    );
+    display.scaling.fractional_scale = wl_request_constructor!(
+        display.client,
+        manager,
+        wp_fractional_scale_manager_v1::get_fractional_scale,
+        std::ptr::addr_of!(wp_fractional_scale_v1_interface),
+        display.surface
+    );
+    (display.client.wl_proxy_add_listener)(
+        display.scaling.fractional_scale as _,
+        std::ptr::addr_of!(FRACTIONAL_SCALE_LISTENER) as _,
+        std::ptr::null_mut(),
+    );
+}
+
+/// The resize_event, if any, is delivered from the main loop
+pub(super) unsafe fn scale_changed() {
+    if !tl_display::is_display_set() {
+        return;
+    }
+    tl_display::with(|display| {
+        if let Some(size) = display.update_framebuffer() {
+            display.pending_resize = Some(size);
+        }
+    });
+}
+
+unsafe extern "C" fn output_handle_geometry(
+    _data: *mut std::ffi::c_void,
+    _output: *mut wl_output,
+    _x: i32,
+    _y: i32,
+    _physical_width: i32,
+    _physical_height: i32,
+    _subpixel: i32,
+    _make: *const ::std::os::raw::c_char,
+    _model: *const ::std::os::raw::c_char,
+    _transform: i32,
+) {
+}
+
+unsafe extern "C" fn output_handle_mode(
+    _data: *mut std::ffi::c_void,
+    _output: *mut wl_output,
+    _flags: u32,
+    _width: i32,
+    _height: i32,
+    _refresh: i32,
+) {
+}
+
+unsafe extern "C" fn output_handle_done(_data: *mut std::ffi::c_void, _output: *mut wl_output) {}
+
+unsafe extern "C" fn output_handle_scale(
+    data: *mut std::ffi::c_void,
+    _output: *mut wl_output,
+    factor: i32,
+) {
+    assert!(!data.is_null());
+    let output: &mut Output = &mut *(data as *mut _);
+    output.scale = factor;
+    scale_changed();
+}
+
+unsafe extern "C" fn surface_handle_enter(
+    _data: *mut std::ffi::c_void,
+    _surface: *mut wl_surface,
+    output: *mut wl_output,
+) {
+    if !tl_display::is_display_set() {
+        return;
+    }
+    tl_display::with(|display| display.scaling.surface_outputs.push(output));
+    scale_changed();
+}
+
+unsafe extern "C" fn surface_handle_leave(
+    _data: *mut std::ffi::c_void,
+    _surface: *mut wl_surface,
+    output: *mut wl_output,
+) {
+    if !tl_display::is_display_set() {
+        return;
+    }
+    tl_display::with(|display| {
+        display
+            .scaling
+            .surface_outputs
+            .retain(|surface_output| *surface_output != output)
+    });
+    scale_changed();
+}
+
+unsafe extern "C" fn fractional_scale_handle_preferred_scale(
+    _data: *mut std::ffi::c_void,
+    _fractional_scale: *mut wp_fractional_scale_v1,
+    scale: u32,
+) {
+    if !tl_display::is_display_set() {
+        return;
+    }
+    tl_display::with(|display| display.scaling.preferred_scale = Some(scale));
+    scale_changed();
+}
+
+/// Applies the scale to the window surface, `width` and `height` are the logical size
+pub(super) unsafe fn set_surface_scale(
+    display: &mut WaylandDisplay,
+    scale: f32,
+    width: i32,
+    height: i32,
+) {
+    if !display.scaling.viewport.is_null() {
+        wl_request!(
+            display.client,
+            display.scaling.viewport,
+            wp_viewport::set_destination,
+            width,
+            height
+        );
+    } else {
+        wl_request!(
+            display.client,
+            display.surface,
+            WL_SURFACE_SET_BUFFER_SCALE,
+            scale as i32
+        );
+    }
+}
@@ -17,23 +17,27 @@ unsafe extern "C" fn create_tmpfile_cloexec(tmpname: *mut libc::c_char) -> libc:
     fd
 }
 
-unsafe extern "C" fn create_anonymous_file(size: usize) -> libc::c_int {
-    let xdg_folder_path = std::env::var("XDG_RUNTIME_DIR").expect("XDG_RUNTIME_DIR not set");
+unsafe fn create_anonymous_file(size: usize) -> std::io::Result<libc::c_int> {
+    let xdg_folder_path = std::env::var("XDG_RUNTIME_DIR").map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "XDG_RUNTIME_DIR not set")
+    })?;
     let filepath = format!("{}/lokinit-shared-XXXXXX", xdg_folder_path);
-    let c_filepath = std::ffi::CString::new(filepath).unwrap();
+    let c_filepath = std::ffi::CString::new(filepath)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
     let fd = create_tmpfile_cloexec(c_filepath.as_ptr() as _);
 
     if fd < 0 {
-        panic!("Cant create temp file");
+        return Err(std::io::Error::last_os_error());
     }
 
+    // posix_fallocate returns the error instead of setting errno
     let ret = libc::posix_fallocate(fd, 0, size as _);
 
     if ret != 0 {
         libc::close(fd);
-        panic!("Cant create temp file")
+        return Err(std::io::Error::from_raw_os_error(ret));
     }
-    fd
+    Ok(fd)
 }
 
 pub unsafe fn create_shm_buffer(
@@ -42,14 +46,11 @@ pub unsafe fn create_shm_buffer(
     width: i32,
     height: i32,
     pixels: &[u8],
-) -> *mut wl_buffer {
+) -> std::io::Result<*mut wl_buffer> {
     let stride = width * 4;
     let length = width * height * 4;
 
-    let fd = create_anonymous_file(length as _);
-    if fd < 0 {
-        panic!("Failed to create temporary file");
-    }
+    let fd = create_anonymous_file(length as _)?;
     let data = libc::mmap(
         std::ptr::null_mut(),
         length as _,
@@ -59,9 +60,10 @@ pub unsafe fn create_shm_buffer(
         0,
     );
 
-    if data == -1 as _ {
+    if data == libc::MAP_FAILED {
+        let error = std::io::Error::last_os_error();
         libc::close(fd);
-        panic!("Failed to mmap temporary file");
+        return Err(error);
     }
 
     let pool = wl_request_constructor!(
@@ -94,5 +96,5 @@ pub unsafe fn create_shm_buffer(
     libc::munmap(data, length as _);
 
     wl_shm_pool_destroy(libwayland, pool);
-    buffer
+    Ok(buffer)
 }
@@ -0,0 +1,239 @@
+//! Input methods with zwp_text_input_v3.
+//! The text input gets enabled whenever our surface has the text input focus and the app
+//! asked for it, the focus is lost on leave and has to be enabled again on the next enter.
+//! Input method events come in batches ended with done, a batch without preedit_string
+//! means the preedit text is gone.
+
+use super::{
+    extensions::text_input::*, libwayland_client::*, tl_display, WaylandDisplay, WaylandPayload,
+};
+use crate::{wl_request, wl_request_constructor};
+
+pub(crate) struct TextInput {
+    text_input: *mut zwp_text_input_v3,
+    /// The app wants text input
+    enabled: bool,
+    /// Our surface has the text input focus
+    entered: bool,
+    /// In surface coordinates
+    cursor_area: (i32, i32, i32, i32),
+    /// A preedit text is shown, an empty one has to be sent when it goes away
+    preedit_shown: bool,
+    pending_preedit: Option<(String, Option<(usize, usize)>)>,
+    pending_commit: Option<String>,
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput {
+            text_input: std::ptr::null_mut(),
+            enabled: false,
+            entered: false,
+            cursor_area: (0, 0, 0, 0),
+            preedit_shown: false,
+            pending_preedit: None,
+            pending_commit: None,
+        }
+    }
+}
+
+static mut TEXT_INPUT_LISTENER: zwp_text_input_v3_listener = zwp_text_input_v3_listener {
+    enter: Some(text_input_handle_enter),
+    leave: Some(text_input_handle_leave),
+    preedit_string: Some(text_input_handle_preedit_string),
+    commit_string: Some(text_input_handle_commit_string),
+    delete_surrounding_text: Some(text_input_handle_delete_surrounding_text),
+    done: Some(text_input_handle_done),
+};
+
+/// Called once the seat is bound, `data` is the WaylandPayload
+pub(super) unsafe fn init(
+    display: &mut WaylandDisplay,
+    seat: *mut wl_seat,
+    data: *mut std::ffi::c_void,
+) {
+    if display.text_input_manager.is_null() {
+        return;
+    }
+    display.text_input.text_input = wl_request_constructor!(
+        display.client,
+        display.text_input_manager,
+        zwp_text_input_manager_v3::get_text_input,
+        std::ptr::addr_of!(zwp_text_input_v3_interface),
+        seat
+    );
+    (display.client.wl_proxy_add_listener)(
+        display.text_input.text_input as _,
+        std::ptr::addr_of!(TEXT_INPUT_LISTENER) as _,
+        data,
+    );
+}
+
+pub(super) unsafe fn set_enabled(display: &mut WaylandDisplay, enabled: bool) {
+    let was_enabled = display.text_input.enabled;
+    display.text_input.enabled = enabled;
+    if !display.text_input.entered || was_enabled == enabled {
+        return;
+    }
+    if enabled {
+        enable(display);
+    } else {
+        let text_input = display.text_input.text_input;
+        wl_request!(display.client, text_input, zwp_text_input_v3::disable);
+        wl_request!(display.client, text_input, zwp_text_input_v3::commit);
+    }
+}
+
+/// `x`, `y`, `width` and `height` are in framebuffer pixels
+pub(super) unsafe fn set_cursor_area(
+    display: &mut WaylandDisplay,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+) {
+    let scale = display.data.dpi_scale;
+    display.text_input.cursor_area = (
+        (x / scale) as i32,
+        (y / scale) as i32,
+        (width / scale).ceil() as i32,
+        (height / scale).ceil() as i32,
+    );
+    if display.text_input.enabled && display.text_input.entered {
+        let text_input = display.text_input.text_input;
+        set_cursor_rectangle(display);
+        wl_request!(display.client, text_input, zwp_text_input_v3::commit);
+    }
+}
+
+unsafe fn enable(display: &mut WaylandDisplay) {
+    let text_input = display.text_input.text_input;
+    wl_request!(display.client, text_input, zwp_text_input_v3::enable);
+    set_cursor_rectangle(display);
+    wl_request!(display.client, text_input, zwp_text_input_v3::commit);
+}
+
+unsafe fn set_cursor_rectangle(display: &mut WaylandDisplay) {
+    let (x, y, width, height) = display.text_input.cursor_area;
+    wl_request!(
+        display.client,
+        display.text_input.text_input,
+        zwp_text_input_v3::set_cursor_rectangle,
+        x,
+        y,
+        width,
+        height
+    );
+}
+
+unsafe extern "C" fn text_input_handle_enter(
+    _data: *mut std::ffi::c_void,
+    _text_input: *mut zwp_text_input_v3,
+    surface: *mut wl_surface,
+) {
+    tl_display::with(|display| {
+        if surface != display.surface {
+            return;
+        }
+        display.text_input.entered = true;
+        if display.text_input.enabled {
+            enable(display);
+        }
+    });
+}
+
+unsafe extern "C" fn text_input_handle_leave(
+    data: *mut std::ffi::c_void,
+    _text_input: *mut zwp_text_input_v3,
+    _surface: *mut wl_surface,
+) {
+    // the compositor disables the text input by itself on leave
+    let preedit_shown = tl_display::with(|display| {
+        let text_input = &mut display.text_input;
+        text_input.entered = false;
+        text_input.pending_preedit = None;
+        text_input.pending_commit = None;
+        std::mem::take(&mut text_input.preedit_shown)
+    });
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+    if let Some(event_handler) = payload.input_handler() {
+        if preedit_shown {
+            event_handler.ime_preedit_event("", None);
+        }
+    }
+}
+
+unsafe extern "C" fn text_input_handle_preedit_string(
+    _data: *mut std::ffi::c_void,
+    _text_input: *mut zwp_text_input_v3,
+    text: *const libc::c_char,
+    cursor_begin: i32,
+    cursor_end: i32,
+) {
+    let text = if text.is_null() {
+        String::new()
+    } else {
+        std::ffi::CStr::from_ptr(text)
+            .to_string_lossy()
+            .into_owned()
+    };
+    // -1 for both is a hidden cursor
+    let cursor = if cursor_begin < 0 || cursor_end < 0 {
+        None
+    } else {
+        Some((cursor_begin as usize, cursor_end as usize))
+    };
+    tl_display::with(|display| display.text_input.pending_preedit = Some((text.clone(), cursor)));
+}
+
+unsafe extern "C" fn text_input_handle_commit_string(
+    _data: *mut std::ffi::c_void,
+    _text_input: *mut zwp_text_input_v3,
+    text: *const libc::c_char,
+) {
+    if text.is_null() {
+        return;
+    }
+    let text = std::ffi::CStr::from_ptr(text)
+        .to_string_lossy()
+        .into_owned();
+    tl_display::with(|display| display.text_input.pending_commit = Some(text.clone()));
+}
+
+unsafe extern "C" fn text_input_handle_delete_surrounding_text(
+    _data: *mut std::ffi::c_void,
+    _text_input: *mut zwp_text_input_v3,
+    _before_length: u32,
+    _after_length: u32,
+) {
+    // surrounding text is never sent, so there is nothing the input method knows to delete
+}
+
+unsafe extern "C" fn text_input_handle_done(
+    data: *mut std::ffi::c_void,
+    _text_input: *mut zwp_text_input_v3,
+    _serial: u32,
+) {
+    let (commit, preedit) = tl_display::with(|display| {
+        let text_input = &mut display.text_input;
+        let commit = text_input.pending_commit.take();
+        let preedit = match text_input.pending_preedit.take() {
+            Some(preedit) => Some(preedit),
+            None if text_input.preedit_shown => Some((String::new(), None)),
+            None => None,
+        };
+        if let Some((text, _)) = &preedit {
+            text_input.preedit_shown = !text.is_empty();
+        }
+        (commit, preedit)
+    });
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+    if let Some(event_handler) = payload.input_handler() {
+        if let Some(text) = commit {
+            event_handler.ime_commit_event(&text);
+        }
+        if let Some((text, cursor)) = preedit {
+            event_handler.ime_preedit_event(&text, cursor);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! wl_touch input.
+//! Touch points keep the id the compositor gave them from down to up, ids are reused
+//! for later touches. wl_touch.up carries no position, so the last one is remembered,
+//! and wl_touch.cancel ends every point at once - the compositor took the sequence
+//! over, e.g. for a gesture of its own.
+
+use super::{libwayland_client::*, tl_display, WaylandDisplay, WaylandPayload};
+use crate::{event::TouchPhase, wl_request_constructor};
+
+use std::collections::HashMap;
+
+pub(crate) struct Touch {
+    pub touch: *mut wl_touch,
+    /// Touch points that started on the window, with their last position in framebuffer pixels
+    points: HashMap<i32, (f32, f32)>,
+}
+
+impl Touch {
+    pub fn new() -> Touch {
+        Touch {
+            touch: std::ptr::null_mut(),
+            points: HashMap::new(),
+        }
+    }
+}
+
+static mut TOUCH_LISTENER: wl_touch_listener = wl_touch_listener {
+    down: Some(touch_handle_down),
+    up: Some(touch_handle_up),
+    motion: Some(touch_handle_motion),
+    frame: Some(touch_handle_frame),
+    cancel: Some(touch_handle_cancel),
+    shape: Some(touch_handle_shape),
+    orientation: Some(touch_handle_orientation),
+};
+
+/// Called from the seat capabilities, `data` is the WaylandPayload
+pub(super) unsafe fn update_capability(
+    display: &mut WaylandDisplay,
+    seat: *mut wl_seat,
+    has_touch: bool,
+    data: *mut std::ffi::c_void,
+) {
+    if has_touch && display.touch.touch.is_null() {
+        display.touch.touch = wl_request_constructor!(
+            display.client,
+            seat,
+            WL_SEAT_GET_TOUCH,
+            display.client.wl_touch_interface
+        );
+        (display.client.wl_proxy_add_listener)(
+            display.touch.touch as _,
+            std::ptr::addr_of!(TOUCH_LISTENER) as _,
+            data,
+        );
+    } else if !has_touch && !display.touch.touch.is_null() {
+        (display.client.wl_proxy_destroy)(display.touch.touch as _);
+        display.touch.touch = std::ptr::null_mut();
+        display.touch.points.clear();
+    }
+}
+
+unsafe fn send_touch_event(
+    data: *mut std::ffi::c_void,
+    phase: TouchPhase,
+    id: i32,
+    x: f32,
+    y: f32,
+) {
+    assert!(!data.is_null());
+    let payload: &mut WaylandPayload = &mut *(data as *mut _);
+    if let Some(event_handler) = payload.input_handler() {
+        event_handler.touch_event(phase, id as u64, x, y);
+    }
+}
+
+unsafe extern "C" fn touch_handle_down(
+    data: *mut std::ffi::c_void,
+    _touch: *mut wl_touch,
+    serial: u32,
+    _time: u32,
+    surface: *mut wl_surface,
+    id: i32,
+    x: wl_fixed_t,
+    y: wl_fixed_t,
+) {
+    let position = tl_display::with(|display| {
+        display.input_serial = serial;
+        // touches on the decorations are not the app's business
+        if surface != display.surface {
+            return None;
+        }
+        // framebuffer pixels with high_dpi, same as the mouse
+        let position = (
+            wl_fixed_to_double(x) as f32 * display.data.dpi_scale,
+            wl_fixed_to_double(y) as f32 * display.data.dpi_scale,
+        );
+        display.touch.points.insert(id, position);
+        Some(position)
+    });
+
+    if let Some((x, y)) = position {
+        send_touch_event(data, TouchPhase::Started, id, x, y);
+    }
+}
+
+unsafe extern "C" fn touch_handle_up(
+    data: *mut std::ffi::c_void,
+    _touch: *mut wl_touch,
+    serial: u32,
+    _time: u32,
+    id: i32,
+) {
+    let position = tl_display::with(|display| {
+        display.input_serial = serial;
+        display.touch.points.remove(&id)
+    });
+
+    if let Some((x, y)) = position {
+        send_touch_event(data, TouchPhase::Ended, id, x, y);
+    }
+}
+
+unsafe extern "C" fn touch_handle_motion(
+    data: *mut std::ffi::c_void,
+    _touch: *mut wl_touch,
+    _time: u32,
+    id: i32,
+    x: wl_fixed_t,
+    y: wl_fixed_t,
+) {
+    let position = tl_display::with(|display| {
+        let dpi_scale = display.data.dpi_scale;
+        let position = display.touch.points.get_mut(&id)?;
+        *position = (
+            wl_fixed_to_double(x) as f32 * dpi_scale,
+            wl_fixed_to_double(y) as f32 * dpi_scale,
+        );
+        Some(*position)
+    });
+
+    if let Some((x, y)) = position {
+        send_touch_event(data, TouchPhase::Moved, id, x, y);
+    }
+}
+
+unsafe extern "C" fn touch_handle_frame(_data: *mut std::ffi::c_void, _touch: *mut wl_touch) {}
+
+unsafe extern "C" fn touch_handle_cancel(data: *mut std::ffi::c_void, _touch: *mut wl_touch) {
+    let points: Vec<_> = tl_display::with(|display| display.touch.points.drain().collect());
+    for (id, (x, y)) in points {
+        send_touch_event(data, TouchPhase::Cancelled, id, x, y);
+    }
+}
+
+unsafe extern "C" fn touch_handle_shape(
+    _data: *mut std::ffi::c_void,
+    _touch: *mut wl_touch,
+    _id: i32,
+    _major: wl_fixed_t,
+    _minor: wl_fixed_t,
+) {
+}
+
+unsafe extern "C" fn touch_handle_orientation(
+    _data: *mut std::ffi::c_void,
+    _touch: *mut wl_touch,
+    _id: i32,
+    _orientation: wl_fixed_t,
+) {
+}
@@ -2,21 +2,30 @@
 //! Spiritual successor of an X11 part of https://github.com/floooh/sokol/blob/master/sokol_app.h
 
 mod clipboard;
+mod drag_n_drop;
 mod glx;
-mod keycodes;
+pub(crate) mod keycodes;
 pub mod libx11;
 mod libx11_ex;
 mod x_cursor;
 mod xi_input;
+mod xim;
 
 use crate::{
-    event::EventHandler,
+    event::{EventHandler, TouchPhase},
     gl,
-    native::{egl, NativeDisplayData},
+    native::{egl, timers, user_events::UserEvents, NativeDisplayData},
     CursorIcon,
 };
 use libx11::*;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 pub struct Dummy;
 
@@ -26,9 +35,20 @@ pub struct X11Display {
     display: *mut Display,
     root: Window,
     window: Window,
+    window_position: (i32, i32),
+    fullscreen: bool,
+    maximized: bool,
+    minimized: bool,
+    visible: bool,
+    /// Position and size to go back to when leaving fullscreen
+    windowed_geometry: Option<((i32, i32), (i32, i32))>,
     data: NativeDisplayData,
     empty_cursor: libx11::Cursor,
     cursor_cache: HashMap<CursorIcon, libx11::Cursor>,
+    dropped_files: Vec<std::path::PathBuf>,
+    xim: Option<xim::Xim>,
+    user_events: Option<Arc<UserEvents>>,
+    timers: timers::Timers,
     get_procaddr: Box<dyn Fn(&str) -> Option<unsafe extern "C" fn()>>,
 }
 
@@ -36,6 +56,8 @@ pub struct X11Display {
 pub struct X11MainLoopData {
     libx11: LibX11,
     libxi: xi_input::LibXi,
+    xdnd: drag_n_drop::Xdnd,
+    user_events: Option<Arc<UserEvents>>,
     display: *mut Display,
     root: Window,
     repeated_keycodes: [bool; 256],
@@ -70,6 +92,12 @@ pub mod tl_display {
         DISPLAY.with(|d| *d.borrow_mut() = Some(display));
         NATIVE_DISPLAY.with(|d| *d.borrow_mut() = Some(with_native_display));
     }
+
+    /// Forgets the display once the window is gone, `window::*` functions can't reach it anymore
+    pub fn clear_display() {
+        NATIVE_DISPLAY.with(|d| *d.borrow_mut() = None);
+        DISPLAY.with(|d| *d.borrow_mut() = None);
+    }
 }
 
 impl crate::native::NativeDisplay for X11Display {
@@ -91,6 +119,18 @@ impl crate::native::NativeDisplay for X11Display {
     fn cancel_quit(&mut self) {
         self.data.quit_requested = false;
     }
+    fn schedule_update(&mut self) {
+        self.data.update_requested = true;
+    }
+    fn event_proxy(&mut self) -> Option<crate::EventProxy> {
+        self.user_events.as_ref().map(UserEvents::proxy)
+    }
+    fn set_timer(&mut self, delay: Duration, repeat: bool) -> u64 {
+        self.timers.set(delay, repeat)
+    }
+    fn clear_timer(&mut self, id: u64) {
+        self.timers.clear(id);
+    }
     fn set_cursor_grab(&mut self, grab: bool) {
         unsafe {
             self.set_cursor_grab(self.window, grab);
@@ -112,8 +152,57 @@ impl crate::native::NativeDisplay for X11Display {
         }
     }
 
-    fn set_window_size(&mut self, _new_width: u32, _new_height: u32) {
-        eprintln!("set_window_size not implemented on linux/x11")
+    fn set_window_size(&mut self, new_width: u32, new_height: u32) {
+        unsafe {
+            let mut width = new_width as i32;
+            let mut height = new_height as i32;
+            self.libx11
+                .update_size_hints(self.display, self.window, |hints| {
+                    let fixed = hints.flags & (PMinSize | PMaxSize) == PMinSize | PMaxSize
+                        && hints.min_width == hints.max_width
+                        && hints.min_height == hints.max_height;
+                    if fixed {
+                        // a window that is not resizable stays so, just at the new size
+                        hints.min_width = width;
+                        hints.min_height = height;
+                        hints.max_width = width;
+                        hints.max_height = height;
+                    } else {
+                        if hints.flags & PMinSize != 0 {
+                            width = width.max(hints.min_width);
+                            height = height.max(hints.min_height);
+                        }
+                        if hints.flags & PMaxSize != 0 {
+                            width = width.min(hints.max_width);
+                            height = height.min(hints.max_height);
+                        }
+                    }
+                    hints.flags |= USSize;
+                    hints.width = width;
+                    hints.height = height;
+                });
+            (self.libx11.XResizeWindow)(self.display, self.window, width as _, height as _);
+            (self.libx11.XFlush)(self.display);
+        }
+    }
+
+    fn set_window_position(&mut self, x: i32, y: i32) {
+        unsafe {
+            // with the StaticGravity from create_window, the position is the one of the window
+            // itself and not of the decorations around it
+            self.libx11
+                .update_size_hints(self.display, self.window, |hints| {
+                    hints.flags |= USPosition;
+                    hints.x = x;
+                    hints.y = y;
+                });
+            (self.libx11.XMoveWindow)(self.display, self.window, x, y);
+            (self.libx11.XFlush)(self.display);
+        }
+    }
+
+    fn window_position(&self) -> (i32, i32) {
+        self.window_position
     }
 
     fn set_fullscreen(&mut self, fullscreen: bool) {
@@ -122,6 +211,51 @@ impl crate::native::NativeDisplay for X11Display {
         }
     }
 
+    fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    fn minimize(&mut self) {
+        unsafe {
+            let screen = (*(self.display as _XPrivDisplay)).default_screen;
+            (self.libx11.XIconifyWindow)(self.display, self.window, screen);
+            (self.libx11.XFlush)(self.display);
+        }
+    }
+
+    fn set_maximized(&mut self, maximized: bool) {
+        let states = [
+            self.libx11.extensions.net_wm_state_maximized_vert,
+            self.libx11.extensions.net_wm_state_maximized_horz,
+        ];
+        unsafe {
+            self.libx11
+                .send_wm_state(self.display, self.root, self.window, maximized, states);
+        }
+    }
+
+    fn is_maximized(&self) -> bool {
+        self.maximized
+    }
+
+    fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    fn set_window_title(&mut self, title: &str) {
+        unsafe {
+            self.libx11
+                .update_window_title(self.display, self.window, title);
+        }
+    }
+
+    fn set_window_icon(&mut self, icon: &crate::conf::Icon) {
+        unsafe {
+            self.libx11
+                .update_window_icon(self.display, self.window, icon);
+        }
+    }
+
     fn clipboard_get(&mut self) -> Option<String> {
         use std::ffi::CString;
 
@@ -155,6 +289,26 @@ impl crate::native::NativeDisplay for X11Display {
         };
     }
 
+    fn dropped_file_count(&mut self) -> usize {
+        self.dropped_files.len()
+    }
+    fn dropped_file_bytes(&mut self, index: usize) -> Option<Vec<u8>> {
+        std::fs::read(self.dropped_files.get(index)?).ok()
+    }
+    fn dropped_file_path(&mut self, index: usize) -> Option<std::path::PathBuf> {
+        self.dropped_files.get(index).cloned()
+    }
+    fn set_ime_enabled(&mut self, enabled: bool) {
+        if let Some(xim) = &mut self.xim {
+            unsafe { xim.set_enabled(enabled) };
+        }
+    }
+    fn set_ime_cursor_area(&mut self, x: f32, y: f32, _width: f32, height: f32) {
+        // XIM only knows about a spot, put it at the bottom left so the candidates don't cover the text
+        if let Some(xim) = &mut self.xim {
+            unsafe { xim.set_spot_location(x as i32, (y + height) as i32) };
+        }
+    }
     fn as_any(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -177,12 +331,26 @@ impl X11Display {
             display: display.display,
             root: display.root,
             window,
+            window_position: display.libx11.query_window_position(
+                display.display,
+                display.root,
+                window,
+            ),
+            fullscreen: false,
+            maximized: false,
+            minimized: false,
+            visible: true,
+            windowed_geometry: None,
             empty_cursor: x_cursor::create_empty_cursor(
                 display.display,
                 display.root,
                 &mut display.libx11,
             ),
             cursor_cache: HashMap::new(),
+            dropped_files: vec![],
+            xim: xim::Xim::new(&display.libx11, display.display, window),
+            user_events: display.user_events.clone(),
+            timers: timers::Timers::new(),
             data: NativeDisplayData {
                 screen_width: w,
                 screen_height: h,
@@ -251,79 +419,109 @@ impl X11Display {
         };
         (libx11.XDefineCursor)(display, window, cursor);
     }
-    // TODO: _fullscreen is not used, this function always setting window fullscreen
-    // should be able to able to go back from fullscreen to windowed instead
-    unsafe fn set_fullscreen(&mut self, window: Window, _fullscreen: bool) {
-        let wm_state = (self.libx11.XInternAtom)(
-            self.display,
-            b"_NET_WM_STATE\x00" as *const u8 as *const _,
-            false as _,
-        );
-        let wm_fullscreen = (self.libx11.XInternAtom)(
-            self.display,
-            b"_NET_WM_STATE_FULLSCREEN\x00" as *const u8 as *const _,
-            false as _,
-        );
-
-        // this is the first method to make window fullscreen
-        // hide it, change _NET_WM_STATE_FULLSCREEN property and than show it back
-        // someone on stackoverflow mentioned that this is not working on ubuntu/unity though
-        {
-            (self.libx11.XLowerWindow)(self.display, window);
-            (self.libx11.XUnmapWindow)(self.display, window);
-            (self.libx11.XSync)(self.display, false as _);
-
-            let mut atoms: [Atom; 2] = [wm_fullscreen, 0 as _];
-            (self.libx11.XChangeProperty)(
-                self.display,
-                window,
-                wm_state,
-                4 as _,
-                32,
-                PropModeReplace,
-                atoms.as_mut_ptr() as *mut _ as *mut _,
-                1,
-            );
-            (self.libx11.XMapWindow)(self.display, window);
-            (self.libx11.XRaiseWindow)(self.display, window);
-            (self.libx11.XFlush)(self.display);
+    unsafe fn set_fullscreen(&mut self, window: Window, fullscreen: bool) {
+        if fullscreen && !self.fullscreen {
+            self.windowed_geometry = Some((
+                self.window_position,
+                (self.data.screen_width, self.data.screen_height),
+            ));
         }
+        let states = [self.libx11.extensions.net_wm_state_fullscreen, 0];
+        self.libx11
+            .send_wm_state(self.display, self.root, window, fullscreen, states);
+    }
 
-        // however, this is X, so just in case - the second method
-        // send ClientMessage to the window with request to change property to fullscreen
-        {
-            let mut data = [0isize; 5];
-
-            data[0] = 1;
-            data[1] = wm_fullscreen as isize;
-            data[2] = 0;
-
-            let mut ev = XClientMessageEvent {
-                type_0: 33,
-                serial: 0,
-                send_event: true as _,
-                message_type: wm_state,
-                window,
-                display: self.display,
-                format: 32,
-                data: ClientMessageData {
-                    l: std::mem::transmute(data),
-                },
-            };
-            (self.libx11.XSendEvent)(
-                self.display as _,
-                self.root,
-                false as _,
-                (1048576 | 131072) as _,
-                &mut ev as *mut XClientMessageEvent as *mut _,
-            );
+    /// Follows the _NET_WM_STATE set by the window manager
+    unsafe fn update_wm_state(&mut self, states: &[Atom]) {
+        let fullscreen = states.contains(&self.libx11.extensions.net_wm_state_fullscreen);
+        if self.fullscreen && !fullscreen {
+            // most window managers put the window back where it was on their own, not all do
+            if let Some(((x, y), (width, height))) = self.windowed_geometry.take() {
+                (self.libx11.XMoveWindow)(self.display, self.window, x, y);
+                (self.libx11.XResizeWindow)(self.display, self.window, width as _, height as _);
+                (self.libx11.XFlush)(self.display);
+            }
         }
+        self.fullscreen = fullscreen;
+        // window managers can also maximize one way only, that does not count
+        self.maximized = states.contains(&self.libx11.extensions.net_wm_state_maximized_vert)
+            && states.contains(&self.libx11.extensions.net_wm_state_maximized_horz);
     }
 }
 
 impl X11MainLoopData {
+    /// Sleeps until the X server sends something, a user event arrives, the next timer is due
+    /// or `timeout` runs out, None to wait without a timeout
+    unsafe fn wait_for_events(&mut self, timeout: Option<Duration>) {
+        // XPending also flushes the requests, the server might only answer to them
+        if (self.libx11.XPending)(self.display) > 0 {
+            return;
+        }
+        let mut pollfds = [
+            libc::pollfd {
+                fd: (*(self.display as _XPrivDisplay)).fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                // negative fds are ignored by poll
+                fd: self.user_events.as_ref().map_or(-1, |events| events.fd()),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let until_timer = tl_display::with(|d| d.timers.next_deadline())
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let timeout = [timeout, until_timer].iter().flatten().min().copied();
+        libc::poll(
+            pollfds.as_mut_ptr(),
+            pollfds.len() as _,
+            timers::poll_timeout(timeout),
+        );
+    }
+
+    fn process_user_events_and_timers(&mut self, event_handler: &mut dyn EventHandler) {
+        if let Some(user_events) = &self.user_events {
+            for payload in user_events.take() {
+                event_handler.user_event(payload);
+            }
+        }
+        let now = Instant::now();
+        while let Some(id) = tl_display::with(|d| d.timers.pop_expired(now)) {
+            event_handler.timer_event(id);
+        }
+    }
+
     unsafe fn process_event(&mut self, event: &mut XEvent, event_handler: &mut dyn EventHandler) {
+        let (filtered, preedit) = tl_display::with(|d| match &mut d.xim {
+            Some(xim) => (xim.filter_event(event), xim.take_preedit()),
+            None => (false, None),
+        });
+        // input usually changes what is on screen,
+        // so the blocking event loop draws a frame after it.
+        // Key, button, motion, crossing and focus events, dropped files and raw motion
+        if preedit.is_some() || matches!((*event).type_0, 2..=10 | 31 | 35) {
+            tl_display::with(|d| d.data.update_requested = true);
+        }
+        if let Some((text, cursor)) = preedit {
+            event_handler.ime_preedit_event(&text, cursor);
+        }
+        if filtered {
+            return;
+        }
+
         match (*event).type_0 {
+            // text committed by the input method, there is no key behind it
+            2 if (*event).xkey.keycode == 0 => {
+                let text = tl_display::with(|d| {
+                    d.xim
+                        .as_mut()
+                        .and_then(|xim| xim.lookup_string(&mut (*event).xkey))
+                });
+                if let Some(text) = text {
+                    event_handler.ime_commit_event(&text);
+                }
+            }
             2 => {
                 let keycode = (*event).xkey.keycode as libc::c_int;
                 let key = keycodes::translate_key(&mut self.libx11, self.display, keycode);
@@ -387,11 +585,49 @@ impl X11MainLoopData {
                     event_handler.mouse_button_up_event(btn, x, y);
                 }
             }
-            7 => {
-                // Mouse Enter
+            // EnterNotify and LeaveNotify, without those caused by set_cursor_grab
+            7 | 8
+                if (*event).xcrossing.mode != NotifyGrab
+                    && (*event).xcrossing.mode != NotifyUngrab =>
+            {
+                if (*event).type_0 == 7 {
+                    event_handler.mouse_enter_event();
+                    let x = (*event).xcrossing.x as libc::c_float;
+                    let y = (*event).xcrossing.y as libc::c_float;
+                    event_handler.mouse_motion_event(x, y);
+                } else {
+                    event_handler.mouse_leave_event();
+                }
+            }
+            // FocusIn and FocusOut, but not those caused by keyboard grabs,
+            // like the window manager taking the keyboard while the window is dragged
+            9 | 10
+                if (*event).xfocus.mode != NotifyGrab && (*event).xfocus.mode != NotifyUngrab =>
+            {
+                event_handler.focus_event((*event).type_0 == 9);
             }
-            8 => {
-                // Mouse Leave
+            // VisibilityNotify, UnmapNotify and MapNotify
+            15 | 18 | 19 => {
+                let visible = match (*event).type_0 {
+                    15 => (*event).xvisibility.state != VisibilityFullyObscured,
+                    18 => false,
+                    _ => true,
+                };
+                if tl_display::with(|d| std::mem::replace(&mut d.visible, visible)) != visible {
+                    event_handler.visibility_event(visible);
+                }
+                // window managers also unmap windows on other workspaces, only iconified
+                // ones are minimized
+                if (*event).type_0 == 18 {
+                    let state = self
+                        .libx11
+                        .query_window_state(self.display, (*event).xunmap.window);
+                    if state == IconicState {
+                        update_minimized(event_handler, true);
+                    }
+                } else if (*event).type_0 == 19 {
+                    update_minimized(event_handler, false);
+                }
             }
             6 => {
                 let x = (*event).xmotion.x as libc::c_float;
@@ -409,10 +645,47 @@ impl X11MainLoopData {
                         d.data.screen_height = height;
                     });
                     event_handler.resize_event(width as _, height as _);
+                    tl_display::with(|d| d.data.update_requested = true);
                 }
+                // synthetic events from the window manager carry root coordinates (ICCCM 4.1.5),
+                // real ones are relative to the parent, which is the frame of the window
+                // manager for decorated windows
+                let position = if (*event).xconfigure.send_event != 0 {
+                    ((*event).xconfigure.x, (*event).xconfigure.y)
+                } else {
+                    self.libx11.query_window_position(
+                        self.display,
+                        self.root,
+                        (*event).xconfigure.window,
+                    )
+                };
+                if position != tl_display::with(|d| d.window_position) {
+                    tl_display::with(|d| d.window_position = position);
+                    event_handler.window_moved_event(position.0, position.1);
+                }
+            }
+            // PropertyNotify
+            28 if (*event).xproperty.atom == self.libx11.extensions.net_wm_state => {
+                let states = self
+                    .libx11
+                    .query_wm_state(self.display, (*event).xproperty.window);
+                tl_display::with(|d| d.update_wm_state(&states));
+                let hidden = states.contains(&self.libx11.extensions.net_wm_state_hidden);
+                update_minimized(event_handler, hidden);
+            }
+            // Expose
+            12 => {
+                tl_display::with(|d| d.data.update_requested = true);
             }
             33 => {
-                if (*event).xclient.message_type == self.libx11.extensions.wm_protocols {
+                let xdnd_message = self.xdnd.handle_client_message(
+                    &mut self.libx11,
+                    self.display,
+                    &(*event).xclient,
+                );
+                if !xdnd_message
+                    && (*event).xclient.message_type == self.libx11.extensions.wm_protocols
+                {
                     let protocol = (*event).xclient.data.l[0 as libc::c_int as usize] as Atom;
                     if protocol == self.libx11.extensions.wm_delete_window {
                         tl_display::with(|d| d.data.quit_requested = true);
@@ -426,6 +699,18 @@ impl X11MainLoopData {
                 // // only UTF8_STRING request is actually supported
                 clipboard::respond_to_clipboard_request(&mut self.libx11, self.display, event);
             }
+            // SelectionNotify
+            31 => {
+                let paths = self.xdnd.handle_selection_notify(
+                    &mut self.libx11,
+                    self.display,
+                    &(*event).xselection,
+                );
+                if let Some(paths) = paths.filter(|paths| !paths.is_empty()) {
+                    tl_display::with(|d| d.dropped_files = paths.clone());
+                    event_handler.files_dropped_event();
+                }
+            }
             // SelectionClear
             29 => {}
             17 => {}
@@ -434,9 +719,27 @@ impl X11MainLoopData {
             35 if Some((*event).xcookie.extension)
                 == (self.libxi).xi_extension_opcode(&mut self.libx11, self.display) =>
             {
-                if (*event).xcookie.evtype == xi_input::XI_RawMotion {
-                    let (dx, dy) = self.libxi.read_cookie(&mut (*event).xcookie, self.display);
-                    event_handler.raw_mouse_motion(dx as f32, dy as f32);
+                match (*event).xcookie.evtype {
+                    xi_input::XI_RawMotion => {
+                        let (dx, dy) = self.libxi.read_cookie(&mut (*event).xcookie, self.display);
+                        event_handler.raw_mouse_motion(dx as f32, dy as f32);
+                    }
+                    // Only the owner of a touch sequence gets its events,
+                    // so unlike on Wayland nothing takes a sequence away halfway
+                    evtype @ (xi_input::XI_TouchBegin
+                    | xi_input::XI_TouchUpdate
+                    | xi_input::XI_TouchEnd) => {
+                        let phase = match evtype {
+                            xi_input::XI_TouchBegin => TouchPhase::Started,
+                            xi_input::XI_TouchUpdate => TouchPhase::Moved,
+                            _ => TouchPhase::Ended,
+                        };
+                        let (id, x, y) = self
+                            .libxi
+                            .read_touch_cookie(&mut (*event).xcookie, self.display);
+                        event_handler.touch_event(phase, id, x as f32, y as f32);
+                    }
+                    _ => {}
                 }
             }
             _ => {}
@@ -453,26 +756,182 @@ impl X11MainLoopData {
     }
 }
 
-unsafe fn glx_main_loop<F>(
-    mut display: X11MainLoopData,
+/// Sends window_minimized_event or window_restored_event, when the state changed
+fn update_minimized(event_handler: &mut dyn EventHandler, minimized: bool) {
+    if tl_display::with(|d| std::mem::replace(&mut d.minimized, minimized)) != minimized {
+        if minimized {
+            event_handler.window_minimized_event();
+        } else {
+            event_handler.window_restored_event();
+        }
+    }
+}
+
+enum GlContext {
+    Glx {
+        glx: glx::Glx,
+        context: glx::GLXContext,
+        window: glx::GLXWindow,
+    },
+    Egl {
+        egl_lib: egl::LibEgl,
+        display: egl::EGLDisplay,
+        surface: egl::EGLSurface,
+    },
+}
+
+/// The window with its GL context and event handler, driven either by `run`
+/// or by the app itself through `lokinit::EventPump`
+pub struct X11Window {
+    display: X11MainLoopData,
+    window: Window,
+    context: GlContext,
+    /// Only None while the window is dropped
+    event_handler: Option<Box<dyn EventHandler>>,
+}
+
+impl X11Window {
+    /// Dispatches the events that arrived, first waiting up to `timeout` for some,
+    /// or forever on None. Returns false once the app should quit.
+    pub fn pump_events(&mut self, timeout: Option<Duration>) -> Result<bool, crate::Error> {
+        unsafe {
+            if let GlContext::Glx {
+                glx,
+                context,
+                window,
+            } = &mut self.context
+            {
+                glx.make_current(self.display.display, *window, *context);
+            }
+            if timeout != Some(Duration::ZERO) {
+                self.display.wait_for_events(timeout);
+            }
+            let count = (self.display.libx11.XPending)(self.display.display);
+
+            let event_handler = &mut **self.event_handler.as_mut().unwrap();
+            for _ in 0..count {
+                let mut xevent = _XEvent { type_0: 0 };
+                (self.display.libx11.XNextEvent)(self.display.display, &mut xevent);
+                self.display.process_event(&mut xevent, event_handler);
+            }
+            self.display.process_user_events_and_timers(event_handler);
+        }
+        if CONNECTION_LOST.load(Ordering::SeqCst) {
+            return Err(crate::Error::Protocol(
+                "lost the connection to the X server".to_string(),
+            ));
+        }
+        Ok(!tl_display::with(|d| d.data.quit_ordered))
+    }
+
+    pub fn swap_buffers(&mut self) {
+        unsafe {
+            match &mut self.context {
+                GlContext::Glx { glx, window, .. } => {
+                    glx.swap_buffers(self.display.display, *window)
+                }
+                GlContext::Egl {
+                    egl_lib,
+                    display,
+                    surface,
+                } => {
+                    (egl_lib.eglSwapBuffers.unwrap())(*display, *surface);
+                }
+            }
+            (self.display.libx11.XFlush)(self.display.display);
+        }
+    }
+
+    pub fn event_handler(&mut self) -> &mut dyn EventHandler {
+        &mut **self.event_handler.as_mut().unwrap()
+    }
+}
+
+impl Drop for X11Window {
+    fn drop(&mut self) {
+        // the app may still free its GL objects or call window::* functions while dropped
+        drop(self.event_handler.take());
+        if let Some(user_events) = &self.display.user_events {
+            user_events.close();
+        }
+        unsafe {
+            match &mut self.context {
+                GlContext::Glx {
+                    glx,
+                    context,
+                    window,
+                } => glx.destroy_context(self.display.display, *window, *context),
+                GlContext::Egl {
+                    egl_lib, display, ..
+                } => {
+                    // also destroys the context and surface
+                    if let Some(terminate) = egl_lib.eglTerminate {
+                        terminate(*display);
+                    }
+                }
+            }
+            // X11Display still needs the connection to let go of the input method
+            tl_display::clear_display();
+            (self.display.libx11.XUnmapWindow)(self.display.display, self.window);
+            (self.display.libx11.XDestroyWindow)(self.display.display, self.window);
+            (self.display.libx11.XCloseDisplay)(self.display.display);
+        }
+    }
+}
+
+/// Set when Xlib reports the connection to the X server as lost
+static CONNECTION_LOST: AtomicBool = AtomicBool::new(false);
+
+/// Keeps Xlib from exiting the process when the connection breaks,
+/// `X11Window::pump_events` reports it as an error instead.
+/// XSetIOErrorExitHandler only exists since libX11 1.7, older ones still exit.
+unsafe fn set_io_error_exit_handler(libx11: &LibX11, display: *mut Display) {
+    type XSetIOErrorExitHandler = unsafe extern "C" fn(
+        _: *mut Display,
+        _: Option<unsafe extern "C" fn(_: *mut Display, _: *mut std::ffi::c_void)>,
+        _: *mut std::ffi::c_void,
+    );
+    unsafe extern "C" fn io_error_exit_handler(
+        _display: *mut Display,
+        _user_data: *mut std::ffi::c_void,
+    ) {
+        CONNECTION_LOST.store(true, Ordering::SeqCst);
+    }
+
+    if let Ok(set_handler) = libx11
+        .module
+        .get_symbol::<XSetIOErrorExitHandler>("XSetIOErrorExitHandler")
+    {
+        set_handler(display, Some(io_error_exit_handler), std::ptr::null_mut());
+    }
+}
+
+enum GlLib {
+    Glx(glx::Glx),
+    Egl(egl::LibEgl),
+}
+
+unsafe fn create_glx_window(
+    display: &mut X11MainLoopData,
+    mut glx: glx::Glx,
     conf: &crate::conf::Conf,
-    f: &mut Option<F>,
-    screen: i32,
-) -> Result<(), X11MainLoopData>
-where
-    F: 'static + FnOnce() -> Box<dyn EventHandler>,
-{
-    let mut glx = match glx::Glx::init(&mut display.libx11, display.display, screen) {
-        Some(glx) => glx,
-        _ => return Err(display),
-    };
+) -> Result<(Window, GlContext), crate::Error> {
     let visual = glx.visual;
     let depth = glx.depth;
-    let window = display
-        .libx11
-        .create_window(display.root, display.display, visual, depth, conf);
-
-    let (glx_context, glx_window) = glx.create_context(display.display, window);
+    let window =
+        display
+            .libx11
+            .create_window(display.root, display.display, visual, depth, conf)?;
+    display
+        .libxi
+        .select_touch_events(&mut display.libx11, display.display, window);
+    display
+        .xdnd
+        .make_aware(&mut display.libx11, display.display, window);
+
+    let (glx_context, glx_window) = glx
+        .create_context(display.display, window)
+        .map_err(|reason| crate::Error::ContextCreationFailed(reason.to_string()))?;
     glx.swap_interval(
         display.display,
         glx_window,
@@ -497,66 +956,43 @@ where
         libgl.get_procaddr(procname)
     });
 
-    tl_display::set_display(X11Display::new(&mut display, window, w, h, get_procaddr));
-
-    if conf.fullscreen {
-        tl_display::with(|d| d.set_fullscreen(window, true));
-    }
+    tl_display::set_display(X11Display::new(display, window, w, h, get_procaddr));
 
-    //let mut display = display.without_skia();
-    let mut event_handler = (f.take().unwrap())();
-
-    while !tl_display::with(|d| d.data.quit_ordered) {
-        glx.make_current(display.display, glx_window, glx_context);
-        let count = (display.libx11.XPending)(display.display);
-
-        for _ in 0..count {
-            let mut xevent = _XEvent { type_0: 0 };
-            (display.libx11.XNextEvent)(display.display, &mut xevent);
-            display.process_event(&mut xevent, &mut *event_handler);
-        }
-
-        {
-            event_handler.update();
-            event_handler.draw();
-        }
-
-        glx.swap_buffers(display.display, glx_window);
-        (display.libx11.XFlush)(display.display);
-    }
-
-    glx.destroy_context(display.display, glx_window, glx_context);
-    (display.libx11.XUnmapWindow)(display.display, window);
-    (display.libx11.XDestroyWindow)(display.display, window);
-    (display.libx11.XCloseDisplay)(display.display);
-
-    Ok(())
+    Ok((
+        window,
+        GlContext::Glx {
+            glx,
+            context: glx_context,
+            window: glx_window,
+        },
+    ))
 }
 
-unsafe fn egl_main_loop<F>(
-    mut display: X11MainLoopData,
+unsafe fn create_egl_window(
+    display: &mut X11MainLoopData,
+    mut egl_lib: egl::LibEgl,
     conf: &crate::conf::Conf,
-    f: &mut Option<F>,
-) -> Result<(), X11MainLoopData>
-where
-    F: 'static + FnOnce() -> Box<dyn EventHandler>,
-{
-    let mut egl_lib = match egl::LibEgl::try_load() {
-        Some(glx) => glx,
-        _ => return Err(display),
-    };
-
-    let window =
-        display
-            .libx11
-            .create_window(display.root, display.display, std::ptr::null_mut(), 0, conf);
+) -> Result<(Window, GlContext), crate::Error> {
+    let window = display.libx11.create_window(
+        display.root,
+        display.display,
+        std::ptr::null_mut(),
+        0,
+        conf,
+    )?;
+    display
+        .libxi
+        .select_touch_events(&mut display.libx11, display.display, window);
+    display
+        .xdnd
+        .make_aware(&mut display.libx11, display.display, window);
 
     let (context, config, egl_display) = egl::create_egl_context(
         &mut egl_lib,
         display.display as *mut _,
         conf.platform.framebuffer_alpha,
     )
-    .unwrap();
+    .map_err(|err| crate::Error::ContextCreationFailed(format!("EGL: {:?}", err)))?;
 
     let egl_surface = (egl_lib.eglCreateWindowSurface.unwrap())(
         egl_display,
@@ -567,10 +1003,14 @@ where
 
     if egl_surface.is_null() {
         // EGL_NO_SURFACE
-        panic!("surface creation failed");
+        return Err(crate::Error::ContextCreationFailed(
+            "EGL: surface creation failed".to_string(),
+        ));
     }
     if (egl_lib.eglMakeCurrent.unwrap())(egl_display, egl_surface, egl_surface, context) == 0 {
-        panic!("eglMakeCurrent failed");
+        return Err(crate::Error::ContextCreationFailed(
+            "EGL: eglMakeCurrent failed".to_string(),
+        ));
     }
 
     crate::native::gl::load_gl_funcs(|proc| {
@@ -589,57 +1029,43 @@ where
         egl_get_procaddr(name.as_ptr())
     });
 
-    tl_display::set_display(X11Display::new(&mut display, window, w, h, get_procaddr));
-
-    if conf.fullscreen {
-        tl_display::with(|d| d.set_fullscreen(window, true));
-    }
+    tl_display::set_display(X11Display::new(display, window, w, h, get_procaddr));
 
     (display.libx11.XFlush)(display.display);
 
-    //let mut display = display.without_skia();
-    let mut event_handler = (f.take().unwrap())();
-
-    while !tl_display::with(|d| d.data.quit_ordered) {
-        let count = (display.libx11.XPending)(display.display);
-
-        for _ in 0..count {
-            let mut xevent = _XEvent { type_0: 0 };
-            (display.libx11.XNextEvent)(display.display, &mut xevent);
-            display.process_event(&mut xevent, &mut *event_handler);
-        }
-
-        {
-            event_handler.update();
-            event_handler.draw();
-        }
-
-        (egl_lib.eglSwapBuffers.unwrap())(egl_display, egl_surface);
-        (display.libx11.XFlush)(display.display);
-    }
-
-    (display.libx11.XUnmapWindow)(display.display, window);
-    (display.libx11.XDestroyWindow)(display.display, window);
-    (display.libx11.XCloseDisplay)(display.display);
-
-    Ok(())
+    Ok((
+        window,
+        GlContext::Egl {
+            egl_lib,
+            display: egl_display,
+            surface: egl_surface,
+        },
+    ))
 }
 
-pub fn run<F>(conf: &crate::conf::Conf, f: &mut Option<F>) -> Option<()>
+/// Opens the window, with the GL library picked according to `conf.platform.linux_x11_gl`
+pub fn create<F>(conf: &crate::conf::Conf, f: &mut Option<F>) -> Result<X11Window, crate::Error>
 where
     F: 'static + FnOnce() -> Box<dyn EventHandler>,
 {
     unsafe {
-        let mut libx11 = LibX11::try_load()?;
-        let libxi = xi_input::LibXi::try_load()?;
+        let mut libx11 = LibX11::try_load()
+            .ok_or_else(|| crate::Error::NoDisplayServer("libX11 not found".to_string()))?;
+        let libxi = xi_input::LibXi::try_load()
+            .ok_or_else(|| crate::Error::NoDisplayServer("libXi not found".to_string()))?;
 
         (libx11.XInitThreads)();
         (libx11.XrmInitialize)();
 
         let x11_display = (libx11.XOpenDisplay)(std::ptr::null());
         if x11_display.is_null() {
-            panic!("XOpenDisplay() failed!");
+            return Err(crate::Error::NoDisplayServer(
+                "XOpenDisplay() failed".to_string(),
+            ));
         }
+        libx11.grab_error_handler();
+        CONNECTION_LOST.store(false, Ordering::SeqCst);
+        set_io_error_exit_handler(&libx11, x11_display);
 
         // screen selection process. The place to do something about
         // proper multi-monitor support
@@ -659,32 +1085,72 @@ where
         (libx11.XkbSetDetectableAutoRepeat)(x11_display, true as _, std::ptr::null_mut());
 
         libx11.load_extensions(x11_display);
-        let display = X11MainLoopData {
+        let xdnd = drag_n_drop::Xdnd::new(&mut libx11, x11_display);
+        let mut display = X11MainLoopData {
             display: x11_display,
             root: x11_root,
             libx11,
             libxi,
+            xdnd,
+            user_events: UserEvents::new(),
             repeated_keycodes: [false; 256],
         };
 
-        match conf.platform.linux_x11_gl {
-            crate::conf::LinuxX11Gl::GLXOnly => {
-                glx_main_loop(display, conf, f, x11_screen).ok().unwrap();
-            }
-            crate::conf::LinuxX11Gl::EGLOnly => {
-                egl_main_loop(display, conf, f).ok().unwrap();
-            }
-            crate::conf::LinuxX11Gl::GLXWithEGLFallback => {
-                if let Err(display) = glx_main_loop(display, conf, f, x11_screen) {
-                    egl_main_loop(display, conf, f).ok().unwrap();
-                }
-            }
-            crate::conf::LinuxX11Gl::EGLWithGLXFallback => {
-                if let Err(display) = egl_main_loop(display, conf, f) {
-                    glx_main_loop(display, conf, f, x11_screen).ok().unwrap();
-                }
+        let glx = |display: &mut X11MainLoopData| {
+            glx::Glx::init(&mut display.libx11, display.display, x11_screen).map(GlLib::Glx)
+        };
+        let egl = || egl::LibEgl::try_load().map(GlLib::Egl);
+        let gl_lib = match conf.platform.linux_x11_gl {
+            crate::conf::LinuxX11Gl::GLXOnly => glx(&mut display),
+            crate::conf::LinuxX11Gl::EGLOnly => egl(),
+            crate::conf::LinuxX11Gl::GLXWithEGLFallback => glx(&mut display).or_else(egl),
+            crate::conf::LinuxX11Gl::EGLWithGLXFallback => egl().or_else(|| glx(&mut display)),
+        };
+        let created = match gl_lib {
+            Some(GlLib::Glx(glx)) => create_glx_window(&mut display, glx, conf),
+            Some(GlLib::Egl(egl_lib)) => create_egl_window(&mut display, egl_lib, conf),
+            None => Err(crate::Error::GlLoadFailed(format!(
+                "no usable GL library for {:?}",
+                conf.platform.linux_x11_gl
+            ))),
+        };
+        let (window, context) = match created {
+            Ok(created) => created,
+            Err(err) => {
+                // also destroys the window, if there is one already
+                (display.libx11.XCloseDisplay)(display.display);
+                return Err(err);
             }
+        };
+
+        tl_display::with(|d| d.data.blocking_event_loop = conf.platform.blocking_event_loop);
+        let event_handler = (f.take().unwrap())();
+
+        Ok(X11Window {
+            display,
+            window,
+            context,
+            event_handler: Some(event_handler),
+        })
+    }
+}
+
+pub fn run(window: &mut X11Window) -> Result<(), crate::Error> {
+    loop {
+        let timeout =
+            if tl_display::with(|d| d.data.blocking_event_loop && !d.data.update_requested) {
+                None
+            } else {
+                Some(Duration::ZERO)
+            };
+        if !window.pump_events(timeout)? {
+            break;
+        }
+        if tl_display::with(|d| d.data.take_update_request()) {
+            window.event_handler().update();
+            window.event_handler().draw();
+            window.swap_buffers();
         }
     }
-    Some(())
+    Ok(())
 }
@@ -0,0 +1,285 @@
+#![allow(non_upper_case_globals)]
+
+//! XDND file drag and drop, only the receiving side.
+//! https://freedesktop.org/wiki/Specifications/XDND/
+//!
+//! The source announces the drag with XdndEnter and the offered types, keeps sending
+//! XdndPosition while the cursor is over our window and we answer each with XdndStatus.
+//! On XdndDrop the data is asked for with XConvertSelection on XdndSelection,
+//! arrives with SelectionNotify, and the source is told the drop is over with XdndFinished.
+
+use super::libx11::*;
+
+use std::path::PathBuf;
+
+/// Newest protocol version we speak
+const XDND_VERSION: libc::c_long = 5;
+const AnyPropertyType: Atom = 0;
+
+pub struct Xdnd {
+    aware: Atom,
+    enter: Atom,
+    position: Atom,
+    status: Atom,
+    leave: Atom,
+    drop: Atom,
+    finished: Atom,
+    selection: Atom,
+    type_list: Atom,
+    action_copy: Atom,
+    uri_list: Atom,
+    window: Window,
+    /// Window the current drag comes from
+    source: Window,
+    /// Protocol version of the source
+    version: libc::c_long,
+    /// The source offers text/uri-list
+    accepted: bool,
+}
+
+unsafe fn intern_atom(libx11: &mut LibX11, display: *mut Display, name: &[u8]) -> Atom {
+    (libx11.XInternAtom)(display, name.as_ptr() as *const _, false as _)
+}
+
+impl Xdnd {
+    pub unsafe fn new(libx11: &mut LibX11, display: *mut Display) -> Xdnd {
+        Xdnd {
+            aware: intern_atom(libx11, display, b"XdndAware\x00"),
+            enter: intern_atom(libx11, display, b"XdndEnter\x00"),
+            position: intern_atom(libx11, display, b"XdndPosition\x00"),
+            status: intern_atom(libx11, display, b"XdndStatus\x00"),
+            leave: intern_atom(libx11, display, b"XdndLeave\x00"),
+            drop: intern_atom(libx11, display, b"XdndDrop\x00"),
+            finished: intern_atom(libx11, display, b"XdndFinished\x00"),
+            selection: intern_atom(libx11, display, b"XdndSelection\x00"),
+            type_list: intern_atom(libx11, display, b"XdndTypeList\x00"),
+            action_copy: intern_atom(libx11, display, b"XdndActionCopy\x00"),
+            uri_list: intern_atom(libx11, display, b"text/uri-list\x00"),
+            window: 0,
+            source: 0,
+            version: 0,
+            accepted: false,
+        }
+    }
+
+    /// Tells drag sources that the window takes drops
+    pub unsafe fn make_aware(
+        &mut self,
+        libx11: &mut LibX11,
+        display: *mut Display,
+        window: Window,
+    ) {
+        self.window = window;
+        let mut version = XDND_VERSION as Atom;
+        (libx11.XChangeProperty)(
+            display,
+            window,
+            self.aware,
+            XA_ATOM,
+            32,
+            PropModeReplace,
+            &mut version as *mut Atom as *mut _,
+            1,
+        );
+    }
+
+    unsafe fn send_message(
+        &self,
+        libx11: &mut LibX11,
+        display: *mut Display,
+        message_type: Atom,
+        data: [libc::c_long; 5],
+    ) {
+        let mut event = XClientMessageEvent {
+            type_0: 33,
+            serial: 0,
+            send_event: true as _,
+            display,
+            window: self.source,
+            message_type,
+            format: 32,
+            data: ClientMessageData { l: data },
+        };
+        (libx11.XSendEvent)(
+            display,
+            self.source,
+            false as _,
+            0, // NoEventMask
+            &mut event as *mut XClientMessageEvent as *mut _,
+        );
+        (libx11.XFlush)(display);
+    }
+
+    unsafe fn finish(&mut self, libx11: &mut LibX11, display: *mut Display, success: bool) {
+        // XdndFinished carries the result since version 5
+        let (success, action) = if success && self.version >= 5 {
+            (1, self.action_copy as libc::c_long)
+        } else {
+            (0, 0)
+        };
+        self.send_message(
+            libx11,
+            display,
+            self.finished,
+            [self.window as _, success, action, 0, 0],
+        );
+        self.source = 0;
+        self.accepted = false;
+    }
+
+    /// Types from XdndEnter, the first three are in the message itself,
+    /// the full list is in the XdndTypeList property of the source window if there are more
+    unsafe fn offered_types(
+        &self,
+        libx11: &mut LibX11,
+        display: *mut Display,
+        event: &XClientMessageEvent,
+    ) -> Vec<Atom> {
+        if event.data.l[1] & 1 == 0 {
+            return event.data.l[2..5]
+                .iter()
+                .map(|atom| *atom as Atom)
+                .collect();
+        }
+
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut count = 0;
+        let mut bytes_after = 0;
+        let mut data = std::ptr::null_mut();
+        (libx11.XGetWindowProperty)(
+            display,
+            self.source,
+            self.type_list,
+            0,
+            i32::MAX as _,
+            false as _,
+            XA_ATOM,
+            &mut actual_type,
+            &mut actual_format,
+            &mut count,
+            &mut bytes_after,
+            &mut data,
+        );
+        if data.is_null() {
+            return vec![];
+        }
+        // format 32 properties are arrays of longs
+        let types = std::slice::from_raw_parts(data as *const Atom, count as _).to_vec();
+        (libx11.XFree)(data as *mut _);
+        types
+    }
+
+    /// Handles an Xdnd ClientMessage, returns false for any other message
+    pub unsafe fn handle_client_message(
+        &mut self,
+        libx11: &mut LibX11,
+        display: *mut Display,
+        event: &XClientMessageEvent,
+    ) -> bool {
+        let message_type = event.message_type;
+        let source = event.data.l[0] as Window;
+
+        if message_type == self.enter {
+            self.source = source;
+            self.version = event.data.l[1] >> 24;
+            self.accepted = self
+                .offered_types(libx11, display, event)
+                .contains(&self.uri_list);
+        } else if message_type == self.position {
+            if source != self.source {
+                return true;
+            }
+            let (accept, action) = if self.accepted {
+                (1, self.action_copy as libc::c_long)
+            } else {
+                (0, 0)
+            };
+            // empty rectangle: send a position for every move
+            self.send_message(
+                libx11,
+                display,
+                self.status,
+                [self.window as _, accept, 0, 0, action],
+            );
+        } else if message_type == self.leave {
+            if source == self.source {
+                self.source = 0;
+                self.accepted = false;
+            }
+        } else if message_type == self.drop {
+            if source != self.source {
+                return true;
+            }
+            if !self.accepted {
+                self.finish(libx11, display, false);
+                return true;
+            }
+            // the timestamp is there since version 1
+            let time = if self.version >= 1 {
+                event.data.l[2] as Time
+            } else {
+                0 // CurrentTime
+            };
+            (libx11.XConvertSelection)(
+                display,
+                self.selection,
+                self.uri_list,
+                self.selection,
+                self.window,
+                time,
+            );
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Handles SelectionNotify, returns the dropped paths if it was the answer to XdndDrop
+    pub unsafe fn handle_selection_notify(
+        &mut self,
+        libx11: &mut LibX11,
+        display: *mut Display,
+        event: &XSelectionEvent,
+    ) -> Option<Vec<PathBuf>> {
+        if event.selection != self.selection || self.source == 0 {
+            return None;
+        }
+        // no property means the source could not convert the selection
+        if event.property == 0 {
+            self.finish(libx11, display, false);
+            return None;
+        }
+
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut size = 0;
+        let mut bytes_after = 0;
+        let mut data = std::ptr::null_mut();
+        (libx11.XGetWindowProperty)(
+            display,
+            self.window,
+            event.property,
+            0,
+            i32::MAX as _,
+            true as _,
+            AnyPropertyType,
+            &mut actual_type,
+            &mut actual_format,
+            &mut size,
+            &mut bytes_after,
+            &mut data,
+        );
+        let paths = if data.is_null() {
+            vec![]
+        } else {
+            let bytes = std::slice::from_raw_parts(data as *const u8, size as _);
+            let paths = crate::native::uri_list::parse_file_paths(bytes);
+            (libx11.XFree)(data as *mut _);
+            paths
+        };
+
+        self.finish(libx11, display, !paths.is_empty());
+        Some(paths)
+    }
+}
@@ -254,16 +254,16 @@ impl Glx {
         //     _sapp_glx_extsupported(b"GLX_ARB_create_context_profile\x00", exts);
 
         let fbconfig = choose_fbconfig(&mut libgl, libx11, display, screen, multisample);
-        assert!(
-            !fbconfig.is_null(),
-            "GLX: Failed to find a suitable GLXFBConfig"
-        );
+        if fbconfig.is_null() {
+            eprintln!("GLX: Failed to find a suitable GLXFBConfig");
+            return None;
+        }
 
         let result = libgl.glxGetVisualFromFBConfig.unwrap()(display, fbconfig);
-        assert!(
-            !result.is_null(),
-            "GLX: Failed to retrieve Visual for GLXFBConfig"
-        );
+        if result.is_null() {
+            eprintln!("GLX: Failed to retrieve Visual for GLXFBConfig");
+            return None;
+        }
 
         let visual = (*result).visual;
         let depth = (*result).depth;
@@ -307,9 +307,9 @@ impl Glx {
         &mut self,
         display: *mut Display,
         window: Window,
-    ) -> (GLXContext, GLXWindow) {
+    ) -> Result<(GLXContext, GLXWindow), &'static str> {
         if self.extensions.glxCreateContextAttribsARB.is_none() {
-            panic!("GLX: ARB_create_context and ARB_create_context_profile required");
+            return Err("GLX: ARB_create_context and ARB_create_context_profile required");
         }
 
         // _sapp_x11_grab_error_handler(libx11);
@@ -330,14 +330,18 @@ impl Glx {
             true as _,
             attribs.as_ptr(),
         );
-        assert!(!glx_ctx.is_null(), "GLX: failed to create GL context");
+        if glx_ctx.is_null() {
+            return Err("GLX: failed to create GL context");
+        }
         // _sapp_x11_release_error_handler(libx11);
 
         let glx_window =
             self.libgl.glxCreateWindow.unwrap()(display, self.fbconfig, window, std::ptr::null());
-        assert!(glx_window != 0, "GLX: failed to create window");
+        if glx_window == 0 {
+            return Err("GLX: failed to create window");
+        }
 
-        (glx_ctx, glx_window)
+        Ok((glx_ctx, glx_window))
     }
 
     pub unsafe fn destroy_context(
@@ -409,7 +413,8 @@ unsafe fn choose_fbconfig(
         (libgl.glxGetFBConfigs.unwrap())(display, screen, &mut native_count);
 
     if native_configs.is_null() || native_count == 0 {
-        panic!("GLX: No GLXFBConfigs returned");
+        eprintln!("GLX: No GLXFBConfigs returned");
+        return std::ptr::null_mut();
     }
 
     let mut usable_configs: Vec<GLFBConfig> = Vec::new();
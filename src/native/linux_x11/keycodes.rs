@@ -5,7 +5,7 @@
 
 use std::cmp::Ordering;
 
-use super::{Display, LibX11};
+use super::{Display, KeySym, LibX11};
 use crate::event::{KeyCode, KeyMods, MouseButton};
 
 pub unsafe fn translate_key(libx11: &mut LibX11, display: *mut Display, scancode: i32) -> KeyCode {
@@ -16,6 +16,12 @@ pub unsafe fn translate_key(libx11: &mut LibX11, display: *mut Display, scancode
 
     let keysym = *keysyms.offset(0 as libc::c_int as isize);
     (libx11.XFree)(keysyms as *mut libc::c_void);
+    translate_keysym(keysym)
+}
+
+/// Maps an unshifted keysym to a `KeyCode`.
+/// Keysyms are shared between X11 and xkbcommon, so the wayland backend uses this table too.
+pub fn translate_keysym(keysym: KeySym) -> KeyCode {
     match keysym {
         65307 => KeyCode::Escape,
         65289 => KeyCode::Tab,
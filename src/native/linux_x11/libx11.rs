@@ -682,6 +682,9 @@ pub mod X_h {
     pub const KeymapStateMask: libc::c_long = (1 as libc::c_long) << 14 as libc::c_int;
 
     pub const GrabModeAsync: libc::c_int = 1 as libc::c_int;
+    pub const NotifyGrab: libc::c_int = 1 as libc::c_int;
+    pub const NotifyUngrab: libc::c_int = 2 as libc::c_int;
+    pub const VisibilityFullyObscured: libc::c_int = 2 as libc::c_int;
 
     pub const ButtonPressMask: libc::c_long = (1 as libc::c_long) << 2 as libc::c_int;
     pub const ButtonReleaseMask: libc::c_long = (1 as libc::c_long) << 3 as libc::c_int;
@@ -704,6 +707,8 @@ pub mod X_h {
     pub const Mod1Mask: libc::c_int = (1 as libc::c_int) << 3 as libc::c_int;
     pub const Mod4Mask: libc::c_int = (1 as libc::c_int) << 6 as libc::c_int;
     pub const PropertyNewValue: libc::c_int = 0 as libc::c_int;
+    pub const XA_ATOM: Atom = 4 as libc::c_int as Atom;
+    pub const XA_CARDINAL: Atom = 6 as libc::c_int as Atom;
 }
 
 pub mod Xutil_h {
@@ -773,6 +778,8 @@ pub mod Xutil_h {
         pub colormap_size: libc::c_int,
         pub bits_per_rgb: libc::c_int,
     }
+    pub const USPosition: libc::c_long = (1 as libc::c_long) << 0 as libc::c_int;
+    pub const USSize: libc::c_long = (1 as libc::c_long) << 1 as libc::c_int;
     pub const PMinSize: libc::c_long = (1 as libc::c_long) << 4 as libc::c_int;
     pub const PMaxSize: libc::c_long = (1 as libc::c_long) << 5 as libc::c_int;
     pub const PWinGravity: libc::c_long = (1 as libc::c_long) << 9 as libc::c_int;
@@ -849,6 +856,8 @@ pub type XChangeProperty = unsafe extern "C" fn(
     _: *const libc::c_uchar,
     _: libc::c_int,
 ) -> libc::c_int;
+pub type XDeleteProperty =
+    unsafe extern "C" fn(_: *mut Display, _: Window, _: Atom) -> libc::c_int;
 pub type XSync = unsafe extern "C" fn(_: *mut Display, _: libc::c_int) -> libc::c_int;
 pub type XSetErrorHandler = unsafe extern "C" fn(_: XErrorHandler) -> XErrorHandler;
 pub type XGetWindowAttributes =
@@ -976,6 +985,32 @@ pub type XCreatePixmapCursor = unsafe extern "C" fn(
 ) -> Cursor;
 pub type XFreePixmap = unsafe extern "C" fn(_: *mut Display, _: Pixmap) -> libc::c_int;
 pub type XDefineCursor = unsafe extern "C" fn(_: *mut Display, _: Window, _: Cursor) -> libc::c_int;
+pub type XResizeWindow = unsafe extern "C" fn(
+    _: *mut Display,
+    _: Window,
+    _: libc::c_uint,
+    _: libc::c_uint,
+) -> libc::c_int;
+pub type XMoveWindow =
+    unsafe extern "C" fn(_: *mut Display, _: Window, _: libc::c_int, _: libc::c_int) -> libc::c_int;
+pub type XIconifyWindow =
+    unsafe extern "C" fn(_: *mut Display, _: Window, _: libc::c_int) -> libc::c_int;
+pub type XGetWMNormalHints = unsafe extern "C" fn(
+    _: *mut Display,
+    _: Window,
+    _: *mut XSizeHints,
+    _: *mut libc::c_long,
+) -> libc::c_int;
+pub type XTranslateCoordinates = unsafe extern "C" fn(
+    _: *mut Display,
+    _: Window,
+    _: Window,
+    _: libc::c_int,
+    _: libc::c_int,
+    _: *mut libc::c_int,
+    _: *mut libc::c_int,
+    _: *mut Window,
+) -> libc::c_int;
 
 #[derive(Clone, Default)]
 pub struct X11Extensions {
@@ -985,6 +1020,12 @@ pub struct X11Extensions {
     pub _wm_state: Atom,
     pub net_wm_name: Atom,
     pub net_wm_icon_name: Atom,
+    pub net_wm_state: Atom,
+    pub net_wm_state_fullscreen: Atom,
+    pub net_wm_state_maximized_vert: Atom,
+    pub net_wm_state_maximized_horz: Atom,
+    pub net_wm_state_hidden: Atom,
+    pub net_wm_icon: Atom,
 }
 
 #[derive(Clone)]
@@ -1004,6 +1045,7 @@ pub struct LibX11 {
     pub XCreateWindow: XCreateWindow,
     pub XSetWMProtocols: XSetWMProtocols,
     pub XChangeProperty: XChangeProperty,
+    pub XDeleteProperty: XDeleteProperty,
     pub XSync: XSync,
     pub XSetErrorHandler: XSetErrorHandler,
     pub XGetWindowAttributes: XGetWindowAttributes,
@@ -1035,6 +1077,11 @@ pub struct LibX11 {
     pub XCreatePixmapCursor: XCreatePixmapCursor,
     pub XFreePixmap: XFreePixmap,
     pub XDefineCursor: XDefineCursor,
+    pub XResizeWindow: XResizeWindow,
+    pub XMoveWindow: XMoveWindow,
+    pub XGetWMNormalHints: XGetWMNormalHints,
+    pub XIconifyWindow: XIconifyWindow,
+    pub XTranslateCoordinates: XTranslateCoordinates,
 }
 
 impl LibX11 {
@@ -1055,6 +1102,7 @@ impl LibX11 {
                 XCreateWindow: module.get_symbol("XCreateWindow").unwrap(),
                 XSetWMProtocols: module.get_symbol("XSetWMProtocols").unwrap(),
                 XChangeProperty: module.get_symbol("XChangeProperty").unwrap(),
+                XDeleteProperty: module.get_symbol("XDeleteProperty").unwrap(),
                 XSync: module.get_symbol("XSync").unwrap(),
                 XSetErrorHandler: module.get_symbol("XSetErrorHandler").unwrap(),
                 XGetWindowAttributes: module.get_symbol("XGetWindowAttributes").unwrap(),
@@ -1088,6 +1136,11 @@ impl LibX11 {
                 XCreatePixmapCursor: module.get_symbol("XCreatePixmapCursor").unwrap(),
                 XFreePixmap: module.get_symbol("XFreePixmap").unwrap(),
                 XDefineCursor: module.get_symbol("XDefineCursor").unwrap(),
+                XResizeWindow: module.get_symbol("XResizeWindow").unwrap(),
+                XMoveWindow: module.get_symbol("XMoveWindow").unwrap(),
+                XGetWMNormalHints: module.get_symbol("XGetWMNormalHints").unwrap(),
+                XIconifyWindow: module.get_symbol("XIconifyWindow").unwrap(),
+                XTranslateCoordinates: module.get_symbol("XTranslateCoordinates").unwrap(),
                 extensions: X11Extensions::default(),
                 module: std::rc::Rc::new(module),
             })
@@ -1126,6 +1179,36 @@ impl LibX11 {
                 b"_NET_WM_ICON_NAME\x00" as *const u8 as *const libc::c_char,
                 false as _,
             ),
+            net_wm_state: (self.XInternAtom)(
+                display,
+                b"_NET_WM_STATE\x00" as *const u8 as *const libc::c_char,
+                false as _,
+            ),
+            net_wm_state_fullscreen: (self.XInternAtom)(
+                display,
+                b"_NET_WM_STATE_FULLSCREEN\x00" as *const u8 as *const libc::c_char,
+                false as _,
+            ),
+            net_wm_state_maximized_vert: (self.XInternAtom)(
+                display,
+                b"_NET_WM_STATE_MAXIMIZED_VERT\x00" as *const u8 as *const libc::c_char,
+                false as _,
+            ),
+            net_wm_state_maximized_horz: (self.XInternAtom)(
+                display,
+                b"_NET_WM_STATE_MAXIMIZED_HORZ\x00" as *const u8 as *const libc::c_char,
+                false as _,
+            ),
+            net_wm_state_hidden: (self.XInternAtom)(
+                display,
+                b"_NET_WM_STATE_HIDDEN\x00" as *const u8 as *const libc::c_char,
+                false as _,
+            ),
+            net_wm_icon: (self.XInternAtom)(
+                display,
+                b"_NET_WM_ICON\x00" as *const u8 as *const libc::c_char,
+                false as _,
+            ),
         };
     }
 }
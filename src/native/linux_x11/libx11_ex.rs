@@ -31,12 +31,18 @@ impl LibX11 {
         dpi_scale
     }
 
+    /// Replace the default Xlib error handler, which exits the process, with one that only logs.
     pub unsafe fn grab_error_handler(&mut self) {
         pub unsafe extern "C" fn _sapp_x11_error_handler(
             mut _display: *mut Display,
             event: *mut XErrorEvent,
         ) -> libc::c_int {
-            println!("Error: {}", (*event).error_code);
+            eprintln!(
+                "X11 error: {}, request {}.{}",
+                (*event).error_code,
+                (*event).request_code,
+                (*event).minor_code
+            );
             0 as libc::c_int
         }
 
@@ -46,11 +52,6 @@ impl LibX11 {
         ));
     }
 
-    pub unsafe fn release_error_handler(&mut self, display: *mut Display) {
-        (self.XSync)(display, false as _);
-        (self.XSetErrorHandler)(None);
-    }
-
     pub unsafe fn query_window_size(
         &mut self,
         display: *mut Display,
@@ -61,13 +62,138 @@ impl LibX11 {
         (attribs.width, attribs.height)
     }
 
+    /// Top left corner of the window on the screen, decorations excluded
+    pub unsafe fn query_window_position(
+        &mut self,
+        display: *mut Display,
+        root: Window,
+        window: Window,
+    ) -> (i32, i32) {
+        let mut x = 0;
+        let mut y = 0;
+        let mut child = 0;
+        (self.XTranslateCoordinates)(display, window, root, 0, 0, &mut x, &mut y, &mut child);
+        (x, y)
+    }
+
+    /// Asks the window manager to add or remove up to two _NET_WM_STATE atoms, 0 for none
+    pub unsafe fn send_wm_state(
+        &mut self,
+        display: *mut Display,
+        root: Window,
+        window: Window,
+        add: bool,
+        states: [Atom; 2],
+    ) {
+        // _NET_WM_STATE_ADD is 1, _NET_WM_STATE_REMOVE is 0,
+        // and the last 1 tells the request comes from a normal application
+        let data = [add as _, states[0] as _, states[1] as _, 1, 0];
+        let mut event = XClientMessageEvent {
+            type_0: 33,
+            serial: 0,
+            send_event: true as _,
+            display,
+            window,
+            message_type: self.extensions.net_wm_state,
+            format: 32,
+            data: ClientMessageData { l: data },
+        };
+        (self.XSendEvent)(
+            display,
+            root,
+            false as _,
+            SubstructureNotifyMask | SubstructureRedirectMask,
+            &mut event as *mut XClientMessageEvent as *mut _,
+        );
+        (self.XFlush)(display);
+    }
+
+    /// The _NET_WM_STATE atoms the window manager has set on the window
+    pub unsafe fn query_wm_state(&mut self, display: *mut Display, window: Window) -> Vec<Atom> {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut count = 0;
+        let mut bytes_after = 0;
+        let mut data = std::ptr::null_mut();
+        (self.XGetWindowProperty)(
+            display,
+            window,
+            self.extensions.net_wm_state,
+            0,
+            i32::MAX as _,
+            false as _,
+            XA_ATOM,
+            &mut actual_type,
+            &mut actual_format,
+            &mut count,
+            &mut bytes_after,
+            &mut data,
+        );
+        if data.is_null() {
+            return vec![];
+        }
+        // format 32 properties are arrays of longs
+        let states = std::slice::from_raw_parts(data as *const Atom, count as _).to_vec();
+        (self.XFree)(data as *mut _);
+        states
+    }
+
+    /// The ICCCM WM_STATE of the window: NormalState, IconicState or WithdrawnState
+    pub unsafe fn query_window_state(&mut self, display: *mut Display, window: Window) -> i32 {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut count = 0;
+        let mut bytes_after = 0;
+        let mut data = std::ptr::null_mut();
+        (self.XGetWindowProperty)(
+            display,
+            window,
+            self.extensions._wm_state,
+            0,
+            2,
+            false as _,
+            self.extensions._wm_state,
+            &mut actual_type,
+            &mut actual_format,
+            &mut count,
+            &mut bytes_after,
+            &mut data,
+        );
+        if data.is_null() {
+            return WithdrawnState;
+        }
+        let state = if count > 0 {
+            *(data as *const libc::c_long) as i32
+        } else {
+            WithdrawnState
+        };
+        (self.XFree)(data as *mut _);
+        state
+    }
+
+    /// Changes some of the size hints set by `create_window`, keeping the others
+    pub unsafe fn update_size_hints(
+        &mut self,
+        display: *mut Display,
+        window: Window,
+        f: impl FnOnce(&mut XSizeHints),
+    ) {
+        let hints = (self.XAllocSizeHints)();
+        let mut supplied = 0;
+        (self.XGetWMNormalHints)(display, window, hints, &mut supplied);
+        f(&mut *hints);
+        (self.XSetWMNormalHints)(display, window, hints);
+        (self.XFree)(hints as *mut libc::c_void);
+    }
+
     pub unsafe fn update_window_title(
         &mut self,
         display: *mut Display,
         window: Window,
         title: &str,
     ) {
-        let c_title = std::ffi::CString::new(title).unwrap();
+        // A C string ends at the first NUL, so does the title
+        let c_title = std::ffi::CString::new(title.split('\0').next().unwrap()).unwrap();
 
         (self.Xutf8SetWMProperties)(
             display,
@@ -103,6 +229,81 @@ impl LibX11 {
         (self.XFlush)(display);
     }
 
+    /// Sets WM_CLASS, X11's closest thing to an application id
+    pub unsafe fn update_window_class(
+        &mut self,
+        display: *mut Display,
+        window: Window,
+        app_id: &str,
+    ) {
+        let c_app_id = std::ffi::CString::new(app_id.split('\0').next().unwrap()).unwrap();
+        let mut class_hint = XClassHint {
+            res_name: c_app_id.as_ptr() as *mut _,
+            res_class: c_app_id.as_ptr() as *mut _,
+        };
+
+        (self.Xutf8SetWMProperties)(
+            display,
+            window,
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            0 as libc::c_int,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut class_hint,
+        );
+        (self.XFlush)(display);
+    }
+
+    pub unsafe fn update_window_icon(
+        &mut self,
+        display: *mut Display,
+        window: Window,
+        icon: &crate::conf::Icon,
+    ) {
+        // _NET_WM_ICON is a list of width, height and then ARGB pixels, for every image.
+        // Format 32 properties are arrays of longs, so 64 bits per pixel on 64 bit systems
+        let mut data: Vec<libc::c_ulong> = vec![];
+        for image in &icon.images {
+            if image.width == 0
+                || image.height == 0
+                || image.rgba.len() != image.width as usize * image.height as usize * 4
+            {
+                eprintln!(
+                    "Skipping {}x{} icon image with {} bytes of pixels",
+                    image.width,
+                    image.height,
+                    image.rgba.len()
+                );
+                continue;
+            }
+            data.push(image.width as _);
+            data.push(image.height as _);
+            data.extend(image.rgba.chunks_exact(4).map(|pixel| {
+                let [r, g, b, a] =
+                    [pixel[0], pixel[1], pixel[2], pixel[3]].map(libc::c_ulong::from);
+                a << 24 | r << 16 | g << 8 | b
+            }));
+        }
+        // without the property the window manager goes back to the default icon
+        if data.is_empty() {
+            (self.XDeleteProperty)(display, window, self.extensions.net_wm_icon);
+        } else {
+            (self.XChangeProperty)(
+                display,
+                window,
+                self.extensions.net_wm_icon,
+                XA_CARDINAL,
+                32,
+                PropModeReplace,
+                data.as_ptr() as *const _,
+                data.len() as _,
+            );
+        }
+        (self.XFlush)(display);
+    }
+
     pub unsafe fn create_window(
         &mut self,
         root: Window,
@@ -110,7 +311,7 @@ impl LibX11 {
         visual: *mut Visual,
         depth: libc::c_int,
         conf: &crate::conf::Conf,
-    ) -> Window {
+    ) -> Result<Window, crate::Error> {
         let mut wa = XSetWindowAttributes {
             background_pixmap: 0,
             background_pixel: 0,
@@ -152,7 +353,6 @@ impl LibX11 {
             | EnterWindowMask
             | LeaveWindowMask
             | PropertyChangeMask;
-        self.grab_error_handler();
 
         let window = (self.XCreateWindow)(
             display,
@@ -168,11 +368,29 @@ impl LibX11 {
             wamask as libc::c_ulong,
             &mut wa,
         );
-        self.release_error_handler(display);
-        assert!(window != 0, "X11: Failed to create window");
+        if window == 0 {
+            return Err(crate::Error::ContextCreationFailed(
+                "X11: Failed to create window".to_string(),
+            ));
+        }
 
         let mut protocols: [Atom; 1] = [self.extensions.wm_delete_window];
         (self.XSetWMProtocols)(display, window, protocols.as_mut_ptr(), 1 as libc::c_int);
+        if conf.fullscreen {
+            // before the window is mapped, the property is set directly instead of asking
+            // the window manager
+            let states: [Atom; 1] = [self.extensions.net_wm_state_fullscreen];
+            (self.XChangeProperty)(
+                display,
+                window,
+                self.extensions.net_wm_state,
+                XA_ATOM,
+                32,
+                PropModeReplace,
+                states.as_ptr() as *const _,
+                1,
+            );
+        }
         let mut hints = (self.XAllocSizeHints)();
         (*hints).flags |= PWinGravity;
         if !conf.window_resizable {
@@ -187,8 +405,14 @@ impl LibX11 {
         (self.XFree)(hints as *mut libc::c_void);
 
         self.update_window_title(display, window, &conf.window_title);
+        if !conf.app_id.is_empty() {
+            self.update_window_class(display, window, &conf.app_id);
+        }
+        if let Some(ref icon) = conf.icon {
+            self.update_window_icon(display, window, icon);
+        }
 
-        window
+        Ok(window)
     }
 
     pub unsafe fn show_window(&mut self, display: *mut Display, window: Window) {
@@ -8,6 +8,12 @@ use super::{
 pub const XIAllDevices: libc::c_int = 0 as libc::c_int;
 pub const XI_RawMotion: libc::c_int = 17 as libc::c_int;
 pub const XI_RawMotionMask: libc::c_int = (1 as libc::c_int) << XI_RawMotion;
+pub const XI_TouchBegin: libc::c_int = 18 as libc::c_int;
+pub const XI_TouchUpdate: libc::c_int = 19 as libc::c_int;
+pub const XI_TouchEnd: libc::c_int = 20 as libc::c_int;
+pub const XI_TouchBeginMask: libc::c_int = (1 as libc::c_int) << XI_TouchBegin;
+pub const XI_TouchUpdateMask: libc::c_int = (1 as libc::c_int) << XI_TouchUpdate;
+pub const XI_TouchEndMask: libc::c_int = (1 as libc::c_int) << XI_TouchEnd;
 
 #[derive(Copy, Clone)]
 #[repr(C)]
@@ -25,6 +31,24 @@ pub struct XIValuatorState {
     pub values: *mut libc::c_double,
 }
 
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct XIButtonState {
+    pub mask_len: libc::c_int,
+    pub mask: *mut libc::c_uchar,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct XIModifierState {
+    pub base: libc::c_int,
+    pub latched: libc::c_int,
+    pub locked: libc::c_int,
+    pub effective: libc::c_int,
+}
+
+pub type XIGroupState = XIModifierState;
+
 pub type Time = libc::c_ulong;
 
 #[derive(Copy, Clone)]
@@ -45,6 +69,33 @@ pub struct XIRawEvent {
     pub raw_values: *mut libc::c_double,
 }
 
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct XIDeviceEvent {
+    pub type_0: libc::c_int,
+    pub serial: libc::c_ulong,
+    pub send_event: libc::c_int,
+    pub display: *mut Display,
+    pub extension: libc::c_int,
+    pub evtype: libc::c_int,
+    pub time: Time,
+    pub deviceid: libc::c_int,
+    pub sourceid: libc::c_int,
+    pub detail: libc::c_int,
+    pub root: Window,
+    pub event: Window,
+    pub child: Window,
+    pub root_x: libc::c_double,
+    pub root_y: libc::c_double,
+    pub event_x: libc::c_double,
+    pub event_y: libc::c_double,
+    pub flags: libc::c_int,
+    pub buttons: XIButtonState,
+    pub valuators: XIValuatorState,
+    pub mods: XIModifierState,
+    pub group: XIGroupState,
+}
+
 type XQueryExtension = fn(
     _: *mut Display,
     _: *const libc::c_char,
@@ -71,6 +122,8 @@ pub struct LibXi {
     XGetEventData: XGetEventData,
     XFreeEventData: XFreeEventData,
     xi_extension_opcode: Option<i32>,
+    /// XInput 2.2 or newer, the first version with touch events
+    touch_supported: bool,
 }
 
 impl LibXi {
@@ -84,6 +137,7 @@ impl LibXi {
                 XGetEventData: module.get_symbol("XGetEventData").unwrap(),
                 XFreeEventData: module.get_symbol("XFreeEventData").unwrap(),
                 xi_extension_opcode: None,
+                touch_supported: false,
                 _module: std::rc::Rc::new(module),
             })
             .ok()
@@ -124,6 +178,7 @@ impl LibXi {
         if (self.XIQueryVersion)(display, &mut major, &mut minor) != 0 {
             return None;
         }
+        self.touch_supported = major > 2 || (major == 2 && minor >= 2);
 
         // select events to listen
         let mut mask = XI_RawMotionMask;
@@ -142,9 +197,32 @@ impl LibXi {
             &mut masks,
             1 as libc::c_int,
         );
+        self.xi_extension_opcode = Some(xi_opcode);
         Some(xi_opcode)
     }
 
+    /// Touch events are selected on the window itself, selecting them on the root window
+    /// would take the touches from every other window.
+    /// Without this the server emulates pointer events from the touches.
+    pub unsafe fn select_touch_events(
+        &mut self,
+        libx11: &mut libx11::LibX11,
+        display: *mut Display,
+        window: Window,
+    ) {
+        if self.xi_extension_opcode(libx11, display).is_none() || !self.touch_supported {
+            return;
+        }
+
+        let mut mask = XI_TouchBeginMask | XI_TouchUpdateMask | XI_TouchEndMask;
+        let mut masks = XIEventMask {
+            deviceid: XIAllDevices,
+            mask_len: ::std::mem::size_of::<libc::c_int>() as _,
+            mask: &mut mask as *mut _ as *mut _,
+        };
+        (self.XISelectEvents)(display, window, &mut masks, 1 as libc::c_int);
+    }
+
     /// Get mouse delta from XI_RawMotion's event XGenericEventCookie data
     pub unsafe fn read_cookie(
         &mut self,
@@ -164,4 +242,24 @@ impl LibXi {
 
         (dx, dy)
     }
+
+    /// Get the touch id and window position from XI_TouchBegin/Update/End's XGenericEventCookie data
+    pub unsafe fn read_touch_cookie(
+        &mut self,
+        xcookie: &mut libx11::XGenericEventCookie,
+        display: *mut Display,
+    ) -> (u64, f64, f64) {
+        (self.XGetEventData)(display, xcookie);
+
+        let device_event = xcookie.data as *mut xi_input::XIDeviceEvent;
+
+        // touch ids are unique for the lifetime of the touch
+        let id = (*device_event).detail as u32 as u64;
+        let x = (*device_event).event_x;
+        let y = (*device_event).event_y;
+
+        (self.XFreeEventData)(display, &mut (*xcookie) as *mut _);
+
+        (id, x, y)
+    }
 }
@@ -0,0 +1,477 @@
+#![allow(non_upper_case_globals, non_snake_case, clippy::upper_case_acronyms)]
+
+//! X Input Method, for typing text that is composed over several key presses (CJK, dead keys).
+//! The input method is asked for on-the-spot preedit: it reports the text being composed
+//! through XIC callbacks and we show it. When it can't, it draws the preedit text itself,
+//! next to the spot location, or in a window of its own.
+//! Events for the input method have to go through XFilterEvent first, and the finished text
+//! arrives as a synthetic KeyPress with keycode 0, to be read with Xutf8LookupString.
+
+use super::libx11::*;
+
+pub type XIM = *mut libc::c_void;
+pub type XIC = *mut libc::c_void;
+type XIMStyle = libc::c_ulong;
+
+const XIMPreeditCallbacks: XIMStyle = 0x0002;
+const XIMPreeditPosition: XIMStyle = 0x0004;
+const XIMPreeditNothing: XIMStyle = 0x0008;
+const XIMPreeditNone: XIMStyle = 0x0010;
+const XIMStatusNothing: XIMStyle = 0x0400;
+const XIMStatusNone: XIMStyle = 0x0800;
+
+/// Input styles in order of preference
+const STYLES: [XIMStyle; 4] = [
+    XIMPreeditCallbacks | XIMStatusNothing,
+    XIMPreeditPosition | XIMStatusNothing,
+    XIMPreeditNothing | XIMStatusNothing,
+    XIMPreeditNone | XIMStatusNone,
+];
+
+const XNQueryInputStyle: &[u8] = b"queryInputStyle\x00";
+const XNInputStyle: &[u8] = b"inputStyle\x00";
+const XNClientWindow: &[u8] = b"clientWindow\x00";
+const XNFocusWindow: &[u8] = b"focusWindow\x00";
+const XNPreeditAttributes: &[u8] = b"preeditAttributes\x00";
+const XNSpotLocation: &[u8] = b"spotLocation\x00";
+const XNPreeditStartCallback: &[u8] = b"preeditStartCallback\x00";
+const XNPreeditDoneCallback: &[u8] = b"preeditDoneCallback\x00";
+const XNPreeditDrawCallback: &[u8] = b"preeditDrawCallback\x00";
+const XNPreeditCaretCallback: &[u8] = b"preeditCaretCallback\x00";
+
+// Xutf8LookupString status
+const XBufferOverflow: libc::c_int = -1;
+const XLookupChars: libc::c_int = 2;
+const XLookupBoth: libc::c_int = 4;
+
+// XIMCaretDirection
+const XIMForwardChar: libc::c_int = 0;
+const XIMBackwardChar: libc::c_int = 1;
+const XIMLineStart: libc::c_int = 8;
+const XIMLineEnd: libc::c_int = 9;
+const XIMAbsolutePosition: libc::c_int = 10;
+
+#[repr(C)]
+struct XIMStyles {
+    count_styles: libc::c_ushort,
+    supported_styles: *mut XIMStyle,
+}
+
+type XIMProc = unsafe extern "C" fn(XIC, XPointer, XPointer);
+
+#[repr(C)]
+struct XIMCallback {
+    client_data: XPointer,
+    callback: XIMProc,
+}
+
+#[repr(C)]
+struct XIMText {
+    length: libc::c_ushort,
+    feedback: *mut libc::c_ulong,
+    encoding_is_wchar: libc::c_int,
+    /// union of char* multi_byte and wchar_t* wide_char
+    string: *mut libc::c_void,
+}
+
+#[repr(C)]
+struct XIMPreeditDrawCallbackStruct {
+    caret: libc::c_int,
+    chg_first: libc::c_int,
+    chg_length: libc::c_int,
+    text: *mut XIMText,
+}
+
+#[repr(C)]
+struct XIMPreeditCaretCallbackStruct {
+    position: libc::c_int,
+    direction: libc::c_int,
+    style: libc::c_int,
+}
+
+#[repr(C)]
+struct XPoint {
+    x: libc::c_short,
+    y: libc::c_short,
+}
+
+type XOpenIM = unsafe extern "C" fn(
+    _: *mut Display,
+    _: *mut libc::c_void,
+    _: *mut libc::c_char,
+    _: *mut libc::c_char,
+) -> XIM;
+type XCloseIM = unsafe extern "C" fn(_: XIM) -> libc::c_int;
+type XGetIMValues = unsafe extern "C" fn(_: XIM, ...) -> *mut libc::c_char;
+type XCreateIC = unsafe extern "C" fn(_: XIM, ...) -> XIC;
+type XDestroyIC = unsafe extern "C" fn(_: XIC);
+type XSetICValues = unsafe extern "C" fn(_: XIC, ...) -> *mut libc::c_char;
+type XSetICFocus = unsafe extern "C" fn(_: XIC);
+type XUnsetICFocus = unsafe extern "C" fn(_: XIC);
+type XVaCreateNestedList = unsafe extern "C" fn(_: libc::c_int, ...) -> *mut libc::c_void;
+type XFilterEvent = unsafe extern "C" fn(_: *mut XEvent, _: Window) -> libc::c_int;
+type Xutf8LookupString = unsafe extern "C" fn(
+    _: XIC,
+    _: *mut XKeyEvent,
+    _: *mut libc::c_char,
+    _: libc::c_int,
+    _: *mut KeySym,
+    _: *mut libc::c_int,
+) -> libc::c_int;
+type XSupportsLocale = unsafe extern "C" fn() -> libc::c_int;
+type XSetLocaleModifiers = unsafe extern "C" fn(_: *const libc::c_char) -> *mut libc::c_char;
+
+/// Text being composed, updated from the XIC callbacks
+#[derive(Default)]
+struct Preedit {
+    text: Vec<char>,
+    caret: usize,
+    changed: bool,
+}
+
+pub struct Xim {
+    XCloseIM: XCloseIM,
+    XDestroyIC: XDestroyIC,
+    XSetICValues: XSetICValues,
+    XSetICFocus: XSetICFocus,
+    XUnsetICFocus: XUnsetICFocus,
+    XVaCreateNestedList: XVaCreateNestedList,
+    XFilterEvent: XFilterEvent,
+    Xutf8LookupString: Xutf8LookupString,
+    XFree: XFree,
+    im: XIM,
+    ic: XIC,
+    style: XIMStyle,
+    enabled: bool,
+    // the XIC keeps pointers to both
+    preedit: Box<Preedit>,
+    _callbacks: Box<[XIMCallback; 4]>,
+}
+
+impl Xim {
+    /// None if there is no input method running
+    pub unsafe fn new(libx11: &LibX11, display: *mut Display, window: Window) -> Option<Xim> {
+        let module = &libx11.module;
+        let XOpenIM: XOpenIM = module.get_symbol("XOpenIM").ok()?;
+        let XGetIMValues: XGetIMValues = module.get_symbol("XGetIMValues").ok()?;
+        let XCreateIC: XCreateIC = module.get_symbol("XCreateIC").ok()?;
+        let XSupportsLocale: XSupportsLocale = module.get_symbol("XSupportsLocale").ok()?;
+        let XSetLocaleModifiers: XSetLocaleModifiers =
+            module.get_symbol("XSetLocaleModifiers").ok()?;
+        let XVaCreateNestedList: XVaCreateNestedList =
+            module.get_symbol("XVaCreateNestedList").ok()?;
+        let XCloseIM: XCloseIM = module.get_symbol("XCloseIM").ok()?;
+
+        // The input method is picked with XMODIFIERS from the environment, and talks in
+        // the encoding of the locale, both only taken into account with the locale set.
+        // The locale is only borrowed for opening the input method, like SDL does.
+        let previous_locale = libc::setlocale(libc::LC_CTYPE, std::ptr::null());
+        let previous_locale = (!previous_locale.is_null())
+            .then(|| std::ffi::CStr::from_ptr(previous_locale).to_owned());
+        libc::setlocale(libc::LC_CTYPE, b"\x00".as_ptr() as *const _);
+        let im = if XSupportsLocale() != 0 {
+            XSetLocaleModifiers(b"\x00".as_ptr() as *const _);
+            XOpenIM(
+                display,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        } else {
+            std::ptr::null_mut()
+        };
+        if let Some(previous_locale) = previous_locale {
+            libc::setlocale(libc::LC_CTYPE, previous_locale.as_ptr());
+        }
+        if im.is_null() {
+            return None;
+        }
+
+        let mut styles: *mut XIMStyles = std::ptr::null_mut();
+        XGetIMValues(
+            im,
+            XNQueryInputStyle.as_ptr(),
+            &mut styles,
+            std::ptr::null_mut::<libc::c_void>(),
+        );
+        let style = if styles.is_null() {
+            None
+        } else {
+            let supported = std::slice::from_raw_parts(
+                (*styles).supported_styles,
+                (*styles).count_styles as usize,
+            );
+            let style = STYLES
+                .iter()
+                .copied()
+                .find(|style| supported.contains(style));
+            (libx11.XFree)(styles as *mut _);
+            style
+        };
+        let Some(style) = style else {
+            XCloseIM(im);
+            return None;
+        };
+
+        let mut preedit = Box::<Preedit>::default();
+        let client_data = &mut *preedit as *mut Preedit as XPointer;
+        let mut callbacks = Box::new([
+            XIMCallback {
+                client_data,
+                callback: std::mem::transmute::<
+                    unsafe extern "C" fn(XIC, XPointer, XPointer) -> libc::c_int,
+                    XIMProc,
+                >(preedit_start),
+            },
+            XIMCallback {
+                client_data,
+                callback: preedit_done,
+            },
+            XIMCallback {
+                client_data,
+                callback: preedit_draw,
+            },
+            XIMCallback {
+                client_data,
+                callback: preedit_caret,
+            },
+        ]);
+
+        let mut spot = XPoint { x: 0, y: 0 };
+        let preedit_attributes = if style & XIMPreeditCallbacks != 0 {
+            XVaCreateNestedList(
+                0,
+                XNPreeditStartCallback.as_ptr(),
+                &mut callbacks[0] as *mut XIMCallback,
+                XNPreeditDoneCallback.as_ptr(),
+                &mut callbacks[1] as *mut XIMCallback,
+                XNPreeditDrawCallback.as_ptr(),
+                &mut callbacks[2] as *mut XIMCallback,
+                XNPreeditCaretCallback.as_ptr(),
+                &mut callbacks[3] as *mut XIMCallback,
+                XNSpotLocation.as_ptr(),
+                &mut spot as *mut XPoint,
+                std::ptr::null_mut::<libc::c_void>(),
+            )
+        } else if style & XIMPreeditPosition != 0 {
+            XVaCreateNestedList(
+                0,
+                XNSpotLocation.as_ptr(),
+                &mut spot as *mut XPoint,
+                std::ptr::null_mut::<libc::c_void>(),
+            )
+        } else {
+            std::ptr::null_mut()
+        };
+
+        let ic = if preedit_attributes.is_null() {
+            XCreateIC(
+                im,
+                XNInputStyle.as_ptr(),
+                style,
+                XNClientWindow.as_ptr(),
+                window,
+                XNFocusWindow.as_ptr(),
+                window,
+                std::ptr::null_mut::<libc::c_void>(),
+            )
+        } else {
+            let ic = XCreateIC(
+                im,
+                XNInputStyle.as_ptr(),
+                style,
+                XNClientWindow.as_ptr(),
+                window,
+                XNFocusWindow.as_ptr(),
+                window,
+                XNPreeditAttributes.as_ptr(),
+                preedit_attributes,
+                std::ptr::null_mut::<libc::c_void>(),
+            );
+            (libx11.XFree)(preedit_attributes);
+            ic
+        };
+        if ic.is_null() {
+            XCloseIM(im);
+            return None;
+        }
+
+        let xim = Xim {
+            XCloseIM,
+            XDestroyIC: module.get_symbol("XDestroyIC").ok()?,
+            XSetICValues: module.get_symbol("XSetICValues").ok()?,
+            XSetICFocus: module.get_symbol("XSetICFocus").ok()?,
+            XUnsetICFocus: module.get_symbol("XUnsetICFocus").ok()?,
+            XVaCreateNestedList,
+            XFilterEvent: module.get_symbol("XFilterEvent").ok()?,
+            Xutf8LookupString: module.get_symbol("Xutf8LookupString").ok()?,
+            XFree: libx11.XFree,
+            im,
+            ic,
+            style,
+            enabled: false,
+            preedit,
+            _callbacks: callbacks,
+        };
+        // disabled until the app asks for it
+        (xim.XUnsetICFocus)(ic);
+        Some(xim)
+    }
+
+    pub unsafe fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if enabled {
+            (self.XSetICFocus)(self.ic);
+        } else {
+            (self.XUnsetICFocus)(self.ic);
+            if !self.preedit.text.is_empty() {
+                self.preedit.text.clear();
+                self.preedit.caret = 0;
+                self.preedit.changed = true;
+            }
+        }
+    }
+
+    /// Where the input method shows the preedit text or its candidates, the text baseline
+    pub unsafe fn set_spot_location(&mut self, x: i32, y: i32) {
+        if self.style & (XIMPreeditCallbacks | XIMPreeditPosition) == 0 {
+            return;
+        }
+        let mut spot = XPoint {
+            x: x.clamp(i16::MIN as _, i16::MAX as _) as _,
+            y: y.clamp(i16::MIN as _, i16::MAX as _) as _,
+        };
+        let preedit_attributes = (self.XVaCreateNestedList)(
+            0,
+            XNSpotLocation.as_ptr(),
+            &mut spot as *mut XPoint,
+            std::ptr::null_mut::<libc::c_void>(),
+        );
+        (self.XSetICValues)(
+            self.ic,
+            XNPreeditAttributes.as_ptr(),
+            preedit_attributes,
+            std::ptr::null_mut::<libc::c_void>(),
+        );
+        (self.XFree)(preedit_attributes);
+    }
+
+    /// True if the event was taken by the input method and should not be processed further
+    pub unsafe fn filter_event(&mut self, event: *mut XEvent) -> bool {
+        self.enabled && (self.XFilterEvent)(event, 0) != 0
+    }
+
+    /// New preedit text and cursor byte range, if it changed since the last call
+    pub fn take_preedit(&mut self) -> Option<(String, Option<(usize, usize)>)> {
+        if !std::mem::take(&mut self.preedit.changed) {
+            return None;
+        }
+        let text: String = self.preedit.text.iter().collect();
+        let caret = self
+            .preedit
+            .text
+            .iter()
+            .take(self.preedit.caret)
+            .map(|c| c.len_utf8())
+            .sum();
+        Some((text, Some((caret, caret))))
+    }
+
+    /// Text of a KeyPress, in particular of the ones with the text committed by the input method
+    pub unsafe fn lookup_string(&mut self, event: *mut XKeyEvent) -> Option<String> {
+        let mut buffer = vec![0u8; 64];
+        loop {
+            let mut keysym = 0;
+            let mut status = 0;
+            let len = (self.Xutf8LookupString)(
+                self.ic,
+                event,
+                buffer.as_mut_ptr() as *mut _,
+                buffer.len() as _,
+                &mut keysym,
+                &mut status,
+            );
+            match status {
+                XBufferOverflow => buffer.resize(len as usize + 1, 0),
+                XLookupChars | XLookupBoth => {
+                    buffer.truncate(len as usize);
+                    return String::from_utf8(buffer).ok();
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Xim {
+    fn drop(&mut self) {
+        unsafe {
+            (self.XDestroyIC)(self.ic);
+            (self.XCloseIM)(self.im);
+        }
+    }
+}
+
+unsafe extern "C" fn preedit_start(
+    _ic: XIC,
+    client_data: XPointer,
+    _call_data: XPointer,
+) -> libc::c_int {
+    let preedit = &mut *(client_data as *mut Preedit);
+    preedit.text.clear();
+    preedit.caret = 0;
+    preedit.changed = true;
+    // no limit on the preedit length
+    -1
+}
+
+unsafe extern "C" fn preedit_done(_ic: XIC, client_data: XPointer, _call_data: XPointer) {
+    let preedit = &mut *(client_data as *mut Preedit);
+    preedit.text.clear();
+    preedit.caret = 0;
+    preedit.changed = true;
+}
+
+unsafe extern "C" fn preedit_draw(_ic: XIC, client_data: XPointer, call_data: XPointer) {
+    let preedit = &mut *(client_data as *mut Preedit);
+    let draw = &*(call_data as *const XIMPreeditDrawCallbackStruct);
+
+    // the text is always in the locale encoding, UTF-8 on any sane system
+    let text: Vec<char> = if draw.text.is_null() || (*draw.text).string.is_null() {
+        vec![]
+    } else if (*draw.text).encoding_is_wchar != 0 {
+        let text = (*draw.text).string as *const u32;
+        std::slice::from_raw_parts(text, (*draw.text).length as usize)
+            .iter()
+            .filter_map(|c| char::from_u32(*c))
+            .collect()
+    } else {
+        let text = std::ffi::CStr::from_ptr((*draw.text).string as *const libc::c_char);
+        text.to_string_lossy().chars().collect()
+    };
+
+    let len = preedit.text.len();
+    let first = (draw.chg_first.max(0) as usize).min(len);
+    let last = (first + draw.chg_length.max(0) as usize).min(len);
+    preedit.text.splice(first..last, text);
+    preedit.caret = (draw.caret.max(0) as usize).min(preedit.text.len());
+    preedit.changed = true;
+}
+
+unsafe extern "C" fn preedit_caret(_ic: XIC, client_data: XPointer, call_data: XPointer) {
+    let preedit = &mut *(client_data as *mut Preedit);
+    let caret = &mut *(call_data as *mut XIMPreeditCaretCallbackStruct);
+
+    let len = preedit.text.len();
+    preedit.caret = match caret.direction {
+        XIMForwardChar => (preedit.caret + 1).min(len),
+        XIMBackwardChar => preedit.caret.saturating_sub(1),
+        XIMLineStart => 0,
+        XIMLineEnd => len,
+        XIMAbsolutePosition => (caret.position.max(0) as usize).min(len),
+        _ => preedit.caret,
+    };
+    // the input method reads the resulting position back
+    caret.position = preedit.caret as _;
+    preedit.changed = true;
+}
@@ -0,0 +1,168 @@
+//! Timers from `window::set_timeout` and `window::set_interval`.
+//! The main loops fire them between events, and a blocking event loop sleeps
+//! no longer than until the next deadline.
+
+use std::time::{Duration, Instant};
+
+struct Timer {
+    id: u64,
+    deadline: Instant,
+    /// None for a one shot timer
+    interval: Option<Duration>,
+}
+
+pub(crate) struct Timers {
+    next_id: u64,
+    timers: Vec<Timer>,
+}
+
+impl Timers {
+    pub fn new() -> Timers {
+        Timers {
+            next_id: 1,
+            timers: vec![],
+        }
+    }
+
+    pub fn set(&mut self, delay: Duration, repeat: bool) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            deadline: Instant::now() + delay,
+            // a zero interval would fire on every iteration of the loop
+            interval: repeat.then_some(delay.max(Duration::from_millis(1))),
+        });
+        id
+    }
+
+    pub fn clear(&mut self, id: u64) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|timer| timer.deadline).min()
+    }
+
+    /// Id of the earliest timer due at `now`. A one shot timer is removed,
+    /// an interval is scheduled again.
+    /// Timers are taken one at a time, so one cleared by the `timer_event`
+    /// of another due at the same time does not fire.
+    pub fn pop_expired(&mut self, now: Instant) -> Option<u64> {
+        let index = self
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, timer)| timer.deadline <= now)
+            .min_by_key(|(_, timer)| (timer.deadline, timer.id))
+            .map(|(index, _)| index)?;
+
+        let timer = &mut self.timers[index];
+        let id = timer.id;
+        match timer.interval {
+            Some(interval) => {
+                timer.deadline += interval;
+                // after a long stall, skip the missed ticks instead of firing them in a burst
+                if timer.deadline <= now {
+                    timer.deadline = now + interval;
+                }
+            }
+            None => {
+                self.timers.remove(index);
+            }
+        }
+        Some(id)
+    }
+}
+
+/// `timeout` in milliseconds for poll, -1 to wait forever on None
+pub(crate) fn poll_timeout(timeout: Option<Duration>) -> libc::c_int {
+    match timeout {
+        // rounded up, otherwise the loop would spin for the last millisecond
+        Some(timeout) => timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32,
+        None => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expired(timers: &mut Timers, now: Instant) -> Vec<u64> {
+        std::iter::from_fn(|| timers.pop_expired(now)).collect()
+    }
+
+    #[test]
+    fn expired_by_deadline() {
+        let mut timers = Timers::new();
+        let late = timers.set(Duration::from_millis(30), false);
+        let early = timers.set(Duration::from_millis(10), false);
+        let interval = timers.set(Duration::from_millis(20), true);
+
+        let now = Instant::now() + Duration::from_secs(1);
+        assert_eq!(expired(&mut timers, now), vec![early, interval, late]);
+        // one shot timers are gone, the interval is due again later
+        assert!(expired(&mut timers, now).is_empty());
+        assert_eq!(
+            expired(&mut timers, now + Duration::from_millis(20)),
+            vec![interval]
+        );
+    }
+
+    #[test]
+    fn interval_skips_missed_ticks() {
+        let start = Instant::now();
+        let mut timers = Timers::new();
+        let id = timers.set(Duration::from_millis(10), true);
+
+        // a stall of a hundred intervals fires the timer once
+        let now = start + Duration::from_secs(1);
+        assert_eq!(expired(&mut timers, now), vec![id]);
+        assert!(expired(&mut timers, now).is_empty());
+        assert_eq!(
+            timers.next_deadline(),
+            Some(now + Duration::from_millis(10))
+        );
+        assert_eq!(
+            expired(&mut timers, now + Duration::from_millis(10)),
+            vec![id]
+        );
+    }
+
+    #[test]
+    fn clear() {
+        let mut timers = Timers::new();
+        let cleared = timers.set(Duration::ZERO, true);
+        let kept = timers.set(Duration::ZERO, false);
+        timers.clear(cleared);
+        // unknown ids are ignored
+        timers.clear(cleared);
+        timers.clear(1000);
+
+        assert_eq!(expired(&mut timers, Instant::now()), vec![kept]);
+        assert_eq!(timers.next_deadline(), None);
+    }
+
+    #[test]
+    fn cleared_while_due() {
+        let mut timers = Timers::new();
+        let first = timers.set(Duration::ZERO, false);
+        let second = timers.set(Duration::from_millis(1), true);
+
+        let now = Instant::now() + Duration::from_secs(1);
+        assert_eq!(timers.pop_expired(now), Some(first));
+        // the timer_event of the first one clears the second
+        timers.clear(second);
+        assert_eq!(timers.pop_expired(now), None);
+    }
+
+    #[test]
+    fn poll_timeout_rounds_up() {
+        assert_eq!(poll_timeout(None), -1);
+        assert_eq!(poll_timeout(Some(Duration::ZERO)), 0);
+        assert_eq!(poll_timeout(Some(Duration::from_micros(1))), 1);
+        assert_eq!(poll_timeout(Some(Duration::from_millis(1))), 1);
+        assert_eq!(poll_timeout(Some(Duration::from_micros(1001))), 2);
+        assert_eq!(poll_timeout(Some(Duration::MAX)), i32::MAX);
+    }
+}
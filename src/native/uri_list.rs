@@ -0,0 +1,76 @@
+//! `text/uri-list` parsing, the format both XDND and wl_data_device use for dropped files.
+//! https://www.rfc-editor.org/rfc/rfc2483#section-5
+
+use std::{ffi::OsString, os::unix::ffi::OsStringExt, path::PathBuf};
+
+/// Paths of the `file://` URIs in the list, other schemes are skipped
+pub(crate) fn parse_file_paths(uri_list: &[u8]) -> Vec<PathBuf> {
+    uri_list
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty() && !line.starts_with(b"#"))
+        .filter_map(file_path)
+        .collect()
+}
+
+fn file_path(uri: &[u8]) -> Option<PathBuf> {
+    let rest = uri.strip_prefix(b"file://")?;
+    // file://hostname/path, the hostname is usually empty or localhost
+    let path = &rest[rest.iter().position(|b| *b == b'/')?..];
+    Some(PathBuf::from(OsString::from_vec(percent_decode(path))))
+}
+
+fn percent_decode(bytes: &[u8]) -> Vec<u8> {
+    let hex = |b: u8| (b as char).to_digit(16).map(|digit| digit as u8);
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                decoded.push(high << 4 | low);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_paths() {
+        let uri_list = b"# dropped from a file manager\r\n\
+            file:///tmp/a%20b.txt\r\n\
+            file://localhost/home/user/c\r\n\
+            http://example.com/d\r\n\
+            \r\n\
+            file://host/e\n\
+            file:no-slashes";
+        assert_eq!(
+            parse_file_paths(uri_list),
+            vec![
+                PathBuf::from("/tmp/a b.txt"),
+                PathBuf::from("/home/user/c"),
+                PathBuf::from("/e"),
+            ]
+        );
+        assert!(parse_file_paths(b"").is_empty());
+        // a host without a path is not a file
+        assert!(parse_file_paths(b"file://host").is_empty());
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode(b"%41%4a%4A"), b"AJJ");
+        assert_eq!(percent_decode(b"caf%C3%A9"), "café".as_bytes());
+        // malformed and truncated escapes are kept as is
+        assert_eq!(percent_decode(b"%zz%4%"), b"%zz%4%");
+        assert_eq!(percent_decode(b"100%"), b"100%");
+    }
+}
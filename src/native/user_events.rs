@@ -0,0 +1,133 @@
+//! User events posted from other threads through `EventProxy`.
+//! The payloads wait in a queue, and an eventfd polled next to the display connection
+//! wakes up the main loop, even when it sleeps with a blocking event loop.
+
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+pub(crate) struct UserEvents {
+    queue: Mutex<Vec<Box<dyn Any + Send>>>,
+    eventfd: libc::c_int,
+    /// Set once the window is gone, proxies may outlive it
+    closed: AtomicBool,
+}
+
+impl UserEvents {
+    pub fn new() -> Option<Arc<UserEvents>> {
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if eventfd < 0 {
+            return None;
+        }
+        Some(Arc::new(UserEvents {
+            queue: Mutex::new(vec![]),
+            eventfd,
+            closed: AtomicBool::new(false),
+        }))
+    }
+
+    /// Readable while there are events in the queue
+    pub fn fd(&self) -> libc::c_int {
+        self.eventfd
+    }
+
+    pub fn send(&self, payload: Box<dyn Any + Send>) {
+        let mut queue = self.queue.lock().unwrap();
+        // nobody would take it
+        if self.closed.load(Ordering::Relaxed) {
+            return;
+        }
+        queue.push(payload);
+        drop(queue);
+        let one = 1u64;
+        unsafe {
+            libc::write(self.eventfd, &one as *const u64 as *const _, 8);
+        }
+    }
+
+    /// Everything sent so far, in order
+    pub fn take(&self) -> Vec<Box<dyn Any + Send>> {
+        // cleared before taking the queue, anything sent later wakes the loop again
+        let mut counter = 0u64;
+        unsafe {
+            libc::read(self.eventfd, &mut counter as *mut u64 as *mut _, 8);
+        }
+        std::mem::take(&mut *self.queue.lock().unwrap())
+    }
+
+    /// Called when the window goes away, later events are dropped
+    pub fn close(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        self.closed.store(true, Ordering::Relaxed);
+        queue.clear();
+    }
+
+    pub fn proxy(self: &Arc<Self>) -> crate::EventProxy {
+        let user_events = self.clone();
+        crate::EventProxy::new(move |payload| user_events.send(payload))
+    }
+}
+
+impl Drop for UserEvents {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.eventfd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readable(user_events: &UserEvents) -> bool {
+        let mut pollfd = libc::pollfd {
+            fd: user_events.fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pollfd, 1, 0) == 1 }
+    }
+
+    fn numbers(payloads: Vec<Box<dyn Any + Send>>) -> Vec<i32> {
+        payloads
+            .into_iter()
+            .map(|payload| *payload.downcast::<i32>().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn send_and_take() {
+        let user_events = UserEvents::new().unwrap();
+        assert!(!readable(&user_events));
+
+        let proxy = user_events.proxy();
+        std::thread::spawn(move || {
+            proxy.send(1);
+            proxy.send(2);
+        })
+        .join()
+        .unwrap();
+        assert!(readable(&user_events));
+
+        assert_eq!(numbers(user_events.take()), vec![1, 2]);
+        assert!(!readable(&user_events));
+        assert!(user_events.take().is_empty());
+    }
+
+    #[test]
+    fn closed() {
+        let user_events = UserEvents::new().unwrap();
+        let proxy = user_events.proxy();
+        proxy.send(1);
+        user_events.close();
+        proxy.send(2);
+
+        assert!(user_events.take().is_empty());
+        assert!(!readable(&user_events));
+    }
+}